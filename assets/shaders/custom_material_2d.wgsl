@@ -1,13 +0,0 @@
-#import bevy_sprite::mesh2d_vertex_output::VertexOutput
-// we can import items from shader modules in the assets folder with a quoted path
-
-@group(2) @binding(0) var<uniform> material_color: vec4<f32>;
-@group(2) @binding(1) var material_color_texture: texture_2d<f32>;
-@group(2) @binding(2) var material_color_sampler: sampler;
-
-@fragment
-fn fragment(
-    mesh: VertexOutput,
-) -> @location(0) vec4<f32> {
-    return material_color * textureSample(material_color_texture, material_color_sampler, mesh.uv);
-}
@@ -0,0 +1,39 @@
+#import bevy_pbr::forward_io::VertexOutput
+
+// Must match the uniform fields of TerrainMaterial, in order
+struct TerrainMaterial {
+    topsoil_color: vec4<f32>,
+    bedrock_color: vec4<f32>,
+    light: vec4<f32>,
+    strip_depth: f32,
+    layer_depth: f32,
+    layer_count: f32,
+    texture_scale: f32,
+}
+
+@group(2) @binding(0) var<uniform> material: TerrainMaterial;
+@group(2) @binding(1) var fill_texture: texture_2d<f32>;
+@group(2) @binding(2) var fill_sampler: sampler;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // uv is (x across the chunk, depth below the surface), both in world units
+    let depth = in.uv.y;
+
+    // Soil darkens towards bedrock in distinct bands
+    let layer = floor(max(depth - material.strip_depth, 0.0) / material.layer_depth);
+    let t = clamp(layer / max(material.layer_count - 1.0, 1.0), 0.0, 1.0);
+    var color = mix(material.topsoil_color, material.bedrock_color, t);
+
+#ifdef VERTEX_COLORS
+    // The strip along the top takes the surface's own color
+    if depth < material.strip_depth {
+        color = in.color;
+    }
+#endif
+
+    // Wrapped by hand, as the default sampler clamps rather than repeats.
+    // Without a texture this samples plain white.
+    let tiled_uv = fract(in.uv * material.texture_scale);
+    return color * textureSample(fill_texture, fill_sampler, tiled_uv) * material.light;
+}
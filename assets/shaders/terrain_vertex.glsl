@@ -1,15 +0,0 @@
-#version 450
-
-layout(location = 0) in vec3 in_position;
-layout(location = 1) in vec2 in_uv;
-
-out vec2 frag_uv;
-
-uniform mat4 model;
-uniform mat4 view;
-uniform mat4 projection;
-
-void main() {
-    frag_uv = in_uv;
-    gl_Position = projection * view * model * vec4(in_position, 1.0);
-}
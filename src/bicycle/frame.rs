@@ -8,8 +8,8 @@ use bevy::prelude::*;
 use crate::bicycle::groupset::spawn_groupset;
 use crate::bicycle::wheel::{spawn_wheel, BicycleWheel};
 use crate::camera::components::FollowCamera;
-use crate::world::plugin::WorldTerrainPlugin;
-use crate::world::resources::TerrainSeed;
+use crate::world::components::TerrainStreamingAnchor;
+use crate::world::terrain_query::TerrainQuery;
 use crate::GameLayer;
 
 #[derive(Component)]
@@ -156,9 +156,9 @@ impl BicycleFrame {
 }
 
 pub fn spawn_frame(world: &mut World) {
-    let mut system_state: SystemState<(Res<TerrainSeed>, Query<&Transform, With<FollowCamera>>)> =
+    let mut system_state: SystemState<(TerrainQuery, Query<&Transform, With<FollowCamera>>)> =
         SystemState::new(world);
-    let (terrain_seed, camera_t) = system_state.get_mut(world);
+    let (terrain, camera_t) = system_state.get_mut(world);
 
     let bicycle_frame = BicycleFrame::new();
     let frame_collider = bicycle_frame.collider();
@@ -168,9 +168,7 @@ pub fn spawn_frame(world: &mut World) {
         camera_pos = camera_t.translation.truncate().as_dvec2();
     }
 
-    let spawn_height: f32 = 50.0
-        + WorldTerrainPlugin::CHUNK_WIDTH
-            * WorldTerrainPlugin::terrain_height_sample(camera_pos.x, terrain_seed.0) as f32;
+    let spawn_height = 50.0 + terrain.height(camera_pos.x) as f32;
 
     info!("SPAWN HEIGHT: {:?}", spawn_height);
 
@@ -178,6 +176,8 @@ pub fn spawn_frame(world: &mut World) {
         .spawn((
             BicycleFrame::new(),
             Name::new("Frame"),
+            // Keep the ground under the bicycle while the camera is elsewhere
+            TerrainStreamingAnchor::default(),
             Transform::from_xyz(camera_pos.x as f32, spawn_height, 0.0),
             RigidBody::Dynamic,
             Mass(10.0),
@@ -185,8 +185,9 @@ pub fn spawn_frame(world: &mut World) {
             CenterOfMass(bevy::prelude::Vec2::ZERO),
             Visibility::Inherited,
             frame_collider,
+            ExternalForce::default().with_persistence(false),
             CollisionMargin(0.5),
-            CollisionLayers::new([GameLayer::Frame], [GameLayer::World]),
+            CollisionLayers::new([GameLayer::Frame], [GameLayer::World, GameLayer::Props]),
         ))
         .id();
 
@@ -271,7 +271,10 @@ fn front_chainring(
         MeshMaterial3d(color_materials.add(StandardMaterial::from_color(GREEN))),
         CollisionLayers::new(
             GameLayer::Groupset,
-            GameLayer::Groupset.to_bits() | GameLayer::World.to_bits() | GameLayer::Chain.to_bits(),
+            GameLayer::Groupset.to_bits()
+                | GameLayer::World.to_bits()
+                | GameLayer::Props.to_bits()
+                | GameLayer::Chain.to_bits(),
         ),
         *t,
     )
@@ -298,7 +301,10 @@ fn rear_cassette(
         MeshMaterial3d(color_materials.add(StandardMaterial::from_color(RED))),
         CollisionLayers::new(
             GameLayer::Groupset,
-            GameLayer::Groupset.to_bits() | GameLayer::World.to_bits() | GameLayer::Chain.to_bits(),
+            GameLayer::Groupset.to_bits()
+                | GameLayer::World.to_bits()
+                | GameLayer::Props.to_bits()
+                | GameLayer::Chain.to_bits(),
         ),
         *t,
     )
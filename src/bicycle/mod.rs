@@ -4,4 +4,7 @@ pub mod systems;
 pub mod chain;
 pub mod frame;
 pub mod groupset;
+pub mod power;
+pub mod resistance;
+pub mod trip;
 pub mod wheel;
@@ -2,15 +2,23 @@ use bevy::prelude::*;
 
 use super::{
     groupset::GroupsetPlugin,
+    power::PowerPlugin,
+    resistance::ResistancePlugin,
     systems::{on_remove_bicyle, spawn_bicycle, BicycleSystems},
+    trip::TripComputerPlugin,
 };
 
 pub struct BicyclePlugin;
 
 impl Plugin for BicyclePlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((GroupsetPlugin,))
-            .add_observer(on_remove_bicyle)
-            .init_resource::<BicycleSystems>();
+        app.add_plugins((
+            GroupsetPlugin,
+            PowerPlugin,
+            ResistancePlugin,
+            TripComputerPlugin,
+        ))
+        .add_observer(on_remove_bicyle)
+        .init_resource::<BicycleSystems>();
     }
 }
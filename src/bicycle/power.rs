@@ -0,0 +1,93 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::GameState;
+
+use super::{frame::BicycleFrame, groupset::Cog, wheel::BicycleWheel};
+
+// Running energy totals since the bicycle was last spawned.
+#[derive(Resource, Default)]
+pub struct EnergyBudget {
+    pub start_height: Option<f64>,
+    pub crank_work: f64,
+    pub kinetic: f64,
+    pub potential: f64,
+    pub rolling_loss: f64,
+    pub drag_loss: f64,
+    // Slowed down by water and mud
+    pub hazard_loss: f64,
+}
+
+impl EnergyBudget {
+    // Whatever the crank put in that isn't explained by the tracked terms
+    // (joint damping, contact friction, solver error).
+    pub fn unaccounted(&self) -> f64 {
+        self.crank_work
+            - self.kinetic
+            - self.potential
+            - self.rolling_loss
+            - self.drag_loss
+            - self.hazard_loss
+    }
+}
+
+pub struct PowerPlugin;
+impl Plugin for PowerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (accumulate_crank_work, measure_mechanical_energy)
+                .chain()
+                .run_if(in_state(GameState::Ready)),
+        )
+        .init_resource::<EnergyBudget>();
+    }
+}
+
+pub fn crank_power(torque: &ExternalTorque, ang_vel: &AngularVelocity) -> f64 {
+    torque.torque() * ang_vel.0
+}
+
+fn accumulate_crank_work(
+    cogs: Query<(&Cog, &AngularVelocity, &ExternalTorque)>,
+    time: Res<Time>,
+    mut energy_budget: ResMut<EnergyBudget>,
+) {
+    for (cog, ang_vel, torque) in cogs.iter() {
+        if cog == &Cog::FrontChainring {
+            energy_budget.crank_work += crank_power(torque, ang_vel) * time.delta_secs_f64();
+        }
+    }
+}
+
+fn measure_mechanical_energy(
+    frame: Query<&Position, With<BicycleFrame>>,
+    bodies: Query<
+        (
+            &ComputedMass,
+            &ComputedAngularInertia,
+            &LinearVelocity,
+            &AngularVelocity,
+        ),
+        Or<(With<BicycleFrame>, With<BicycleWheel>, With<Cog>)>,
+    >,
+    gravity: Res<Gravity>,
+    mut energy_budget: ResMut<EnergyBudget>,
+) {
+    let Ok(frame_pos) = frame.get_single() else {
+        return;
+    };
+
+    let start_height = *energy_budget.start_height.get_or_insert(frame_pos.y);
+
+    let mut total_mass = 0.0;
+    let mut kinetic = 0.0;
+    for (mass, inertia, lin_vel, ang_vel) in bodies.iter() {
+        total_mass += mass.value();
+        kinetic += 0.5 * mass.value() * lin_vel.length_squared()
+            + 0.5 * inertia.value() * ang_vel.0 * ang_vel.0;
+    }
+
+    energy_budget.kinetic = kinetic;
+    energy_budget.potential = total_mass * gravity.0.length() * (frame_pos.y - start_height);
+}
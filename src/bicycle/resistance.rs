@@ -0,0 +1,113 @@
+use avian2d::{math::Vector, prelude::*};
+use bevy::prelude::*;
+
+use crate::{
+    environment::resources::Weather,
+    world::{components::TerrainSurface, terrain_query::TerrainQuery},
+    GameState,
+};
+
+use super::{frame::BicycleFrame, groupset::Cog, power::EnergyBudget, wheel::BicycleWheel};
+
+#[derive(Resource)]
+pub struct ResistanceCoefficients {
+    // Used where a grounded tire isn't touching a surfaced terrain segment
+    pub rolling_resistance: f64,
+    pub drag_area: f64,
+    pub air_density: f64,
+}
+
+impl Default for ResistanceCoefficients {
+    fn default() -> Self {
+        ResistanceCoefficients {
+            rolling_resistance: 0.005,
+            drag_area: 0.001,
+            air_density: 1.0,
+        }
+    }
+}
+
+// Forces slowing the bicycle down, with what they take out booked against
+// the energy budget
+pub struct ResistancePlugin;
+impl Plugin for ResistancePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (apply_rolling_resistance, apply_air_drag).run_if(in_state(GameState::Ready)),
+        )
+        .init_resource::<ResistanceCoefficients>();
+    }
+}
+
+fn apply_rolling_resistance(
+    mut frame: Query<(&Position, &LinearVelocity, &mut ExternalForce), With<BicycleFrame>>,
+    wheels: Query<&CollidingEntities, With<BicycleWheel>>,
+    surfaces: Query<&TerrainSurface>,
+    bodies: Query<&ComputedMass, Or<(With<BicycleFrame>, With<BicycleWheel>, With<Cog>)>>,
+    terrain: TerrainQuery,
+    coefficients: Res<ResistanceCoefficients>,
+    gravity: Res<Gravity>,
+    time: Res<Time>,
+    mut energy_budget: ResMut<EnergyBudget>,
+) {
+    let Ok((position, lin_vel, mut force)) = frame.get_single_mut() else {
+        return;
+    };
+
+    // Rolling resistance only acts while a tire is on the ground, using the
+    // surface under each grounded tire where there is one
+    let grounded_coefficients: Vec<f64> = wheels
+        .iter()
+        .filter(|colliding| !colliding.is_empty())
+        .map(|colliding| {
+            colliding
+                .iter()
+                .find_map(|entity| surfaces.get(*entity).ok())
+                .map(|surface| surface.0.rolling_resistance())
+                .unwrap_or(coefficients.rolling_resistance)
+        })
+        .collect();
+
+    if grounded_coefficients.is_empty() {
+        return;
+    }
+
+    let rolling_resistance =
+        grounded_coefficients.iter().sum::<f64>() / grounded_coefficients.len() as f64;
+    let total_mass: f64 = bodies.iter().map(|mass| mass.value()).sum();
+
+    // The tires resist rolling along the ground, not the bicycle bouncing
+    // off it, and press into it with the share of weight normal to it
+    let tangent = Vector::new(1.0, terrain.slope(position.x)).normalize();
+    let rolling_speed = lin_vel.dot(tangent);
+    let rolling_force = rolling_resistance * total_mass * gravity.0.length() * tangent.x;
+
+    if rolling_speed != 0.0 {
+        force.apply_force(-rolling_speed.signum() * tangent * rolling_force);
+        energy_budget.rolling_loss += rolling_force * rolling_speed.abs() * time.delta_secs_f64();
+    }
+}
+
+// Drag acts against the air speed, so a tailwind can push the bicycle along
+fn apply_air_drag(
+    mut frame: Query<(&LinearVelocity, &mut ExternalForce), With<BicycleFrame>>,
+    coefficients: Res<ResistanceCoefficients>,
+    weather: Res<Weather>,
+    time: Res<Time>,
+    mut energy_budget: ResMut<EnergyBudget>,
+) {
+    let Ok((lin_vel, mut force)) = frame.get_single_mut() else {
+        return;
+    };
+
+    let air_velocity = lin_vel.0 - Vector::new(weather.wind, 0.0);
+    let drag = -0.5
+        * coefficients.air_density
+        * coefficients.drag_area
+        * air_velocity.length()
+        * air_velocity;
+
+    force.apply_force(drag);
+    energy_budget.drag_loss -= drag.dot(lin_vel.0) * time.delta_secs_f64();
+}
@@ -11,6 +11,8 @@ use super::{
     frame::spawn_frame,
     frame::BicycleFrame,
     groupset::Cog,
+    power::EnergyBudget,
+    trip::TripComputer,
     wheel::BicycleWheel,
 };
 
@@ -42,6 +44,9 @@ pub(crate) fn spawn_bicycle(world: &mut World, bicycle: &mut QueryState<Entity,
         world.entity_mut(bicycle_ent).despawn_recursive();
     }
 
+    world.insert_resource(EnergyBudget::default());
+    world.insert_resource(TripComputer::default());
+
     world.spawn((
         Bicycle,
         Name::new("Bicycle"),
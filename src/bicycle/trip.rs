@@ -0,0 +1,184 @@
+use avian2d::prelude::*;
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+use crate::GameState;
+
+use super::{
+    frame::BicycleFrame,
+    groupset::{ang_vel_to_rpm, Cog},
+    power::crank_power,
+};
+
+// Below this speed the bicycle is considered stopped and moving time is paused
+const MOVING_SPEED_THRESHOLD: f64 = 5.0;
+
+// Distance and climbing from successive positions of the frame. Movement
+// smaller than the deadbands is held back instead of dropped, so physics
+// jitter while standing still isn't counted but slow riding still adds up.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Odometer {
+    // Last position counted towards the distance
+    reference_position: Option<DVec2>,
+    // Height elevation changes are measured from
+    reference_height: f64,
+    pub distance: f64,
+    pub elevation_gain: f64,
+    pub elevation_loss: f64,
+}
+
+impl Odometer {
+    const DISTANCE_DEADBAND: f64 = 1.0;
+    const ELEVATION_DEADBAND: f64 = 5.0;
+
+    // Returns the distance counted by this update
+    pub fn update(&mut self, position: DVec2) -> f64 {
+        let Some(reference_position) = self.reference_position else {
+            self.reference_position = Some(position);
+            self.reference_height = position.y;
+            return 0.0;
+        };
+
+        let mut travelled = 0.0;
+        let step = position.distance(reference_position);
+        if step >= Self::DISTANCE_DEADBAND {
+            travelled = step;
+            self.distance += step;
+            self.reference_position = Some(position);
+        }
+
+        let climb = position.y - self.reference_height;
+        if climb >= Self::ELEVATION_DEADBAND {
+            self.elevation_gain += climb;
+            self.reference_height = position.y;
+        } else if climb <= -Self::ELEVATION_DEADBAND {
+            self.elevation_loss -= climb;
+            self.reference_height = position.y;
+        }
+
+        travelled
+    }
+}
+
+// Totals since the bicycle was last spawned, like a bike computer's trip page.
+#[derive(Resource, Default)]
+pub struct TripComputer {
+    pub odometer: Odometer,
+    pub max_speed: f64,
+    pub ride_time: f64,
+    pub moving_time: f64,
+    // Distance covered while moving, so crawling doesn't inflate the average
+    moving_distance: f64,
+    cadence_time_integral: f64,
+    power_time_integral: f64,
+}
+
+impl TripComputer {
+    pub fn average_speed(&self) -> f64 {
+        if self.moving_time > 0.0 {
+            self.moving_distance / self.moving_time
+        } else {
+            0.0
+        }
+    }
+
+    pub fn average_cadence(&self) -> f64 {
+        if self.moving_time > 0.0 {
+            self.cadence_time_integral / self.moving_time
+        } else {
+            0.0
+        }
+    }
+
+    pub fn average_power(&self) -> f64 {
+        if self.moving_time > 0.0 {
+            self.power_time_integral / self.moving_time
+        } else {
+            0.0
+        }
+    }
+}
+
+pub struct TripComputerPlugin;
+impl Plugin for TripComputerPlugin {
+    fn build(&self, app: &mut App) {
+        // Sampled once per physics step, after the positions have settled
+        app.add_systems(
+            FixedPostUpdate,
+            update_trip_computer
+                .after(PhysicsSet::StepSimulation)
+                .run_if(in_state(GameState::Ready)),
+        )
+        .init_resource::<TripComputer>();
+    }
+}
+
+fn update_trip_computer(
+    frame: Query<(&Position, &LinearVelocity), With<BicycleFrame>>,
+    cogs: Query<(&Cog, &AngularVelocity, &ExternalTorque)>,
+    time: Res<Time>,
+    mut trip: ResMut<TripComputer>,
+) {
+    let Ok((position, lin_vel)) = frame.get_single() else {
+        return;
+    };
+
+    let dt = time.delta_secs_f64();
+    let speed = lin_vel.length();
+    let moving = speed > MOVING_SPEED_THRESHOLD;
+
+    let travelled = trip.odometer.update(position.0);
+    if moving {
+        trip.moving_distance += travelled;
+    }
+
+    trip.max_speed = trip.max_speed.max(speed);
+    trip.ride_time += dt;
+
+    if moving {
+        trip.moving_time += dt;
+
+        for (cog, ang_vel, torque) in cogs.iter() {
+            if cog == &Cog::FrontChainring {
+                trip.cadence_time_integral += ang_vel_to_rpm(ang_vel.0) * dt;
+                trip.power_time_integral += crank_power(torque, ang_vel) * dt;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::DVec2;
+
+    use super::Odometer;
+
+    #[test]
+    fn jitter_inside_the_deadbands_is_ignored() {
+        let mut odometer = Odometer::default();
+        for i in 0..1000 {
+            let wobble = if i % 2 == 0 { 0.1 } else { -0.1 };
+            odometer.update(DVec2::new(wobble, wobble));
+        }
+
+        assert_eq!(odometer.distance, 0.0);
+        assert_eq!(odometer.elevation_gain, 0.0);
+        assert_eq!(odometer.elevation_loss, 0.0);
+    }
+
+    #[test]
+    fn slow_movement_still_accumulates() {
+        let mut odometer = Odometer::default();
+        for i in 0..=1000 {
+            odometer.update(DVec2::new(i as f64 * 0.1, i as f64 * 0.05));
+        }
+
+        // Anything still inside a deadband at the end hasn't been counted yet
+        let path_length = 1000.0 * DVec2::new(0.1, 0.05).length();
+        assert!(odometer.distance <= path_length + 1e-9);
+        assert!(odometer.distance > path_length - Odometer::DISTANCE_DEADBAND);
+        assert!(odometer.elevation_gain <= 50.0 + 1e-9);
+        assert!(odometer.elevation_gain > 50.0 - Odometer::ELEVATION_DEADBAND);
+        assert_eq!(odometer.elevation_loss, 0.0);
+    }
+}
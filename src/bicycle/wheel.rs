@@ -54,11 +54,12 @@ pub fn spawn_wheel(In(wheel): In<BicycleWheel>, world: &mut World) {
             Name::new("Wheel"),
             RigidBody::Dynamic,
             Collider::circle(BicycleWheel::size() as f64),
-            CollisionLayers::new([GameLayer::Wheels], [GameLayer::World]),
+            CollisionLayers::new([GameLayer::Wheels], [GameLayer::World, GameLayer::Props]),
             DebugRender::default().with_collider_color(BLACK.into()),
             Mass(1.0),
             Friction::new(1.0),
             Restitution::new(0.0),
+            CollidingEntities::default(),
             Sprite3dBuilder {
                 image: png_assets.assets.get("bicycle_wheel").unwrap().clone(),
                 pixels_per_metre: 2.5,
@@ -0,0 +1,338 @@
+use std::collections::VecDeque;
+
+#[derive(Clone)]
+pub struct BoundedQueue<T> {
+    queue: VecDeque<T>,
+    max_size: usize,
+    // Sequence number that will be given to the next enqueued item
+    next_sequence: u64,
+    // Running totals, so mean and variance don't need a pass over the queue.
+    // They're taken relative to `shift`, a value near the samples, since
+    // squaring large values that barely differ loses all their precision.
+    shift: f64,
+    shifted_sum: f64,
+    shifted_sum_of_squares: f64,
+    evictions_since_resync: usize,
+    // Monotonic (sequence, value) deques giving amortised O(1) min / max
+    minimums: VecDeque<(u64, f64)>,
+    maximums: VecDeque<(u64, f64)>,
+    ema_alpha: f64,
+    ema: Option<f64>,
+}
+
+impl<T> BoundedQueue<T> {
+    pub fn new(max_size: usize) -> Self {
+        BoundedQueue {
+            queue: VecDeque::with_capacity(max_size),
+            max_size,
+            next_sequence: 0,
+            shift: 0.0,
+            shifted_sum: 0.0,
+            shifted_sum_of_squares: 0.0,
+            evictions_since_resync: 0,
+            minimums: VecDeque::new(),
+            maximums: VecDeque::new(),
+            ema_alpha: 0.1,
+            ema: None,
+        }
+    }
+
+    // Smoothing factor of the exponential moving average, in (0, 1].
+    // Higher values follow new samples more closely.
+    pub fn with_ema_alpha(mut self, alpha: f64) -> Self {
+        self.ema_alpha = alpha.clamp(f64::EPSILON, 1.0);
+        self
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.queue.front() // Peek at the front item
+    }
+
+    pub fn latest(&self) -> Option<&T> {
+        self.queue.back() // Peek at the most recently enqueued item
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len() // Get the current length of the queue
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty() // Check if the queue is empty
+    }
+
+    pub fn iter(&self) -> std::collections::vec_deque::Iter<'_, T> {
+        self.queue.iter() // Iterate from oldest to newest without consuming
+    }
+
+    fn front_sequence(&self) -> u64 {
+        self.next_sequence - self.queue.len() as u64
+    }
+}
+
+impl<T: Copy + Into<f64>> BoundedQueue<T> {
+    pub fn enqueue(&mut self, item: T) {
+        if self.queue.len() == self.max_size {
+            self.dequeue(); // Remove the oldest item
+        }
+
+        let value: f64 = item.into();
+        if self.queue.is_empty() {
+            self.shift = value;
+            self.shifted_sum = 0.0;
+            self.shifted_sum_of_squares = 0.0;
+        }
+
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.queue.push_back(item); // Add the new item
+
+        let shifted = value - self.shift;
+        self.shifted_sum += shifted;
+        self.shifted_sum_of_squares += shifted * shifted;
+
+        while self.minimums.back().is_some_and(|&(_, min)| min >= value) {
+            self.minimums.pop_back();
+        }
+        self.minimums.push_back((sequence, value));
+
+        while self.maximums.back().is_some_and(|&(_, max)| max <= value) {
+            self.maximums.pop_back();
+        }
+        self.maximums.push_back((sequence, value));
+
+        self.ema = Some(match self.ema {
+            Some(ema) => ema + self.ema_alpha * (value - ema),
+            None => value,
+        });
+    }
+
+    pub fn dequeue(&mut self) -> Option<T> {
+        let sequence = self.front_sequence();
+        let item = self.queue.pop_front()?; // Remove the front item
+        let value: f64 = item.into();
+
+        if self
+            .minimums
+            .front()
+            .is_some_and(|&(seq, _)| seq == sequence)
+        {
+            self.minimums.pop_front();
+        }
+        if self
+            .maximums
+            .front()
+            .is_some_and(|&(seq, _)| seq == sequence)
+        {
+            self.maximums.pop_front();
+        }
+
+        let shifted = value - self.shift;
+        self.shifted_sum -= shifted;
+        self.shifted_sum_of_squares -= shifted * shifted;
+
+        // Subtracting evicted samples slowly accumulates rounding error,
+        // so rebuild the totals once per full turnover of the queue.
+        self.evictions_since_resync += 1;
+        if self.evictions_since_resync >= self.max_size {
+            self.resync_totals();
+        }
+
+        Some(item)
+    }
+
+    // Also moves the shift to the current mean, following samples that drift
+    fn resync_totals(&mut self) {
+        self.evictions_since_resync = 0;
+        let Some(mean) = self.mean() else {
+            return;
+        };
+
+        self.shift = mean;
+        self.shifted_sum = 0.0;
+        self.shifted_sum_of_squares = 0.0;
+        for &item in self.queue.iter() {
+            let shifted = item.into() - self.shift;
+            self.shifted_sum += shifted;
+            self.shifted_sum_of_squares += shifted * shifted;
+        }
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.shift * self.queue.len() as f64 + self.shifted_sum
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        if self.queue.is_empty() {
+            return None;
+        }
+        Some(self.shift + self.shifted_sum / self.queue.len() as f64)
+    }
+
+    // Population variance of the samples currently in the queue
+    pub fn variance(&self) -> Option<f64> {
+        if self.queue.is_empty() {
+            return None;
+        }
+        let count = self.queue.len() as f64;
+        let shifted_mean = self.shifted_sum / count;
+        Some((self.shifted_sum_of_squares / count - shifted_mean * shifted_mean).max(0.0))
+    }
+
+    pub fn std_dev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.minimums.front().map(|&(_, min)| min)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.maximums.front().map(|&(_, max)| max)
+    }
+
+    // Exponential moving average over every sample ever enqueued
+    pub fn ema(&self) -> Option<f64> {
+        self.ema
+    }
+}
+
+impl<'a, T> IntoIterator for &'a BoundedQueue<T> {
+    type Item = &'a T;
+    type IntoIter = std::collections::vec_deque::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BoundedQueue;
+
+    fn naive_mean(values: &[f64]) -> f64 {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+
+    #[test]
+    fn empty_queue_has_no_statistics() {
+        let queue: BoundedQueue<f64> = BoundedQueue::new(4);
+
+        assert!(queue.is_empty());
+        assert_eq!(queue.mean(), None);
+        assert_eq!(queue.variance(), None);
+        assert_eq!(queue.min(), None);
+        assert_eq!(queue.max(), None);
+        assert_eq!(queue.ema(), None);
+    }
+
+    #[test]
+    fn evicts_oldest_item_when_full() {
+        let mut queue = BoundedQueue::new(3);
+        for value in [1.0, 2.0, 3.0, 4.0] {
+            queue.enqueue(value);
+        }
+
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.peek(), Some(&2.0));
+        assert_eq!(queue.latest(), Some(&4.0));
+        assert_eq!(
+            queue.iter().copied().collect::<Vec<_>>(),
+            vec![2.0, 3.0, 4.0]
+        );
+    }
+
+    #[test]
+    fn iteration_does_not_consume() {
+        let mut queue = BoundedQueue::new(3);
+        queue.enqueue(1.0);
+        queue.enqueue(2.0);
+
+        let first: f64 = queue.iter().sum();
+        let second: f64 = (&queue).into_iter().sum();
+
+        assert_eq!(first, 3.0);
+        assert_eq!(second, 3.0);
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn running_statistics_match_window() {
+        let values = [4.0, -2.0, 7.5, 3.0, 3.0, 10.0, -6.0, 1.0];
+        let mut queue = BoundedQueue::new(5);
+
+        for (i, &value) in values.iter().enumerate() {
+            queue.enqueue(value);
+
+            let window = &values[(i + 1).saturating_sub(5)..=i];
+            let mean = naive_mean(window);
+            let variance =
+                window.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / window.len() as f64;
+
+            assert!((queue.sum() - window.iter().sum::<f64>()).abs() < 1e-9);
+            assert!((queue.mean().unwrap() - mean).abs() < 1e-9);
+            assert!((queue.variance().unwrap() - variance).abs() < 1e-9);
+            assert_eq!(
+                queue.min().unwrap(),
+                window.iter().copied().fold(f64::MAX, f64::min)
+            );
+            assert_eq!(
+                queue.max().unwrap(),
+                window.iter().copied().fold(f64::MIN, f64::max)
+            );
+        }
+    }
+
+    #[test]
+    fn dequeue_updates_statistics() {
+        let mut queue = BoundedQueue::new(4);
+        for value in [9.0, 1.0, 5.0] {
+            queue.enqueue(value);
+        }
+
+        assert_eq!(queue.dequeue(), Some(9.0));
+        assert_eq!(queue.max(), Some(5.0));
+        assert_eq!(queue.min(), Some(1.0));
+        assert_eq!(queue.mean(), Some(3.0));
+    }
+
+    #[test]
+    fn exponential_moving_average() {
+        let mut queue = BoundedQueue::new(10).with_ema_alpha(0.5);
+        queue.enqueue(2.0);
+        assert_eq!(queue.ema(), Some(2.0));
+
+        queue.enqueue(4.0);
+        assert_eq!(queue.ema(), Some(3.0));
+
+        queue.enqueue(4.0);
+        assert_eq!(queue.ema(), Some(3.5));
+    }
+
+    #[test]
+    fn totals_stay_accurate_over_many_evictions() {
+        let mut queue = BoundedQueue::new(100);
+        for i in 0..100_000 {
+            queue.enqueue(1e6 + (i % 7) as f64 * 0.1);
+        }
+
+        let expected = naive_mean(&queue.iter().copied().collect::<Vec<_>>());
+        assert!((queue.mean().unwrap() - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn variance_is_accurate_with_a_large_offset() {
+        let mut queue = BoundedQueue::new(100);
+        for i in 0..10_000 {
+            queue.enqueue(1e6 + (i % 10) as f64 * 0.01);
+        }
+
+        // The last 100 samples cycle through 0.00 to 0.09 above a million
+        let window: Vec<f64> = queue.iter().copied().collect();
+        let mean = naive_mean(&window);
+        let expected = window.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / window.len() as f64;
+
+        assert!((expected - 8.25e-4).abs() < 1e-9);
+        assert!((queue.variance().unwrap() - expected).abs() < 1e-9);
+        assert!((queue.std_dev().unwrap() - expected.sqrt()).abs() < 1e-7);
+    }
+}
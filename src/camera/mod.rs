@@ -1,4 +1,5 @@
 pub mod components;
 pub mod events;
+pub mod parallax;
 pub mod plugin;
 pub mod systems;
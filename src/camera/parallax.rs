@@ -0,0 +1,209 @@
+use bevy::{
+    image::{ImageAddressMode, ImageLoaderSettings, ImageSampler, ImageSamplerDescriptor},
+    math::Affine2,
+    prelude::*,
+};
+
+use super::{components::FollowCamera, plugin::CameraPlugin};
+
+// Behind the terrain (z = 10), counted back from the camera
+const PARALLAX_NEAREST_Z: f32 = -100.0;
+
+// When a layer is shown. Day and night layers fade across each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerTime {
+    Always,
+    Day,
+    Night,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParallaxLayer {
+    pub image: String,
+    // How far the layer scrolls with the camera: 0.0 stays put on screen,
+    // 1.0 moves with the terrain
+    pub depth: f32,
+    // Bottom edge and height of the layer, as fractions of the view's height
+    pub bottom: f32,
+    pub height: f32,
+    // Times the image repeats up the layer; 1.0 fits it to the layer's height
+    pub vertical_tiles: f32,
+    pub time: LayerTime,
+}
+
+// Drawn back to front
+#[derive(Resource, Clone, PartialEq)]
+pub struct ParallaxLayers(pub Vec<ParallaxLayer>);
+
+impl Default for ParallaxLayers {
+    fn default() -> Self {
+        ParallaxLayers(vec![
+            ParallaxLayer {
+                image: "media/sky-stars.png".to_string(),
+                depth: 0.01,
+                bottom: 0.0,
+                height: 1.0,
+                vertical_tiles: 8.0,
+                time: LayerTime::Night,
+            },
+            ParallaxLayer {
+                image: "media/sky-clouds.png".to_string(),
+                depth: 0.02,
+                bottom: 0.0,
+                height: 1.0,
+                vertical_tiles: 6.0,
+                time: LayerTime::Day,
+            },
+            ParallaxLayer {
+                image: "media/mills-back.png".to_string(),
+                depth: 0.1,
+                bottom: 0.0,
+                height: 0.5,
+                vertical_tiles: 1.0,
+                time: LayerTime::Always,
+            },
+            ParallaxLayer {
+                image: "media/mills-front.png".to_string(),
+                depth: 0.25,
+                bottom: 0.0,
+                height: 0.35,
+                vertical_tiles: 1.0,
+                time: LayerTime::Always,
+            },
+        ])
+    }
+}
+
+// Index into ParallaxLayers of the quad drawing it
+#[derive(Component)]
+pub struct ParallaxLayerQuad(pub usize);
+
+impl CameraPlugin {
+    // (Re)builds the layer quads whenever ParallaxLayers changes. They're
+    // children of the camera, so they stay in view whatever the camera mode.
+    pub fn spawn_parallax_layers(
+        mut commands: Commands,
+        layers: Res<ParallaxLayers>,
+        camera: Query<Entity, With<FollowCamera>>,
+        quads: Query<Entity, With<ParallaxLayerQuad>>,
+        asset_server: Res<AssetServer>,
+        mut meshes: ResMut<Assets<Mesh>>,
+        mut materials: ResMut<Assets<StandardMaterial>>,
+    ) {
+        if !layers.is_changed() {
+            return;
+        }
+        let Ok(camera_id) = camera.get_single() else {
+            return;
+        };
+
+        for quad in quads.iter() {
+            commands.entity(quad).despawn_recursive();
+        }
+
+        let quad_mesh = meshes.add(Rectangle::new(1.0, 1.0));
+
+        for (index, layer) in layers.0.iter().enumerate() {
+            // Tiled by the uv transform, so the image has to repeat
+            let image = asset_server.load_with_settings(
+                layer.image.clone(),
+                |settings: &mut ImageLoaderSettings| {
+                    settings.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+                        address_mode_u: ImageAddressMode::Repeat,
+                        address_mode_v: ImageAddressMode::Repeat,
+                        ..ImageSamplerDescriptor::nearest()
+                    });
+                },
+            );
+
+            commands
+                .spawn((
+                    Name::new(format!("Parallax Layer ({})", layer.image)),
+                    ParallaxLayerQuad(index),
+                    Mesh3d(quad_mesh.clone()),
+                    MeshMaterial3d(materials.add(StandardMaterial {
+                        base_color_texture: Some(image),
+                        alpha_mode: AlphaMode::Blend,
+                        unlit: true,
+                        ..default()
+                    })),
+                    Transform::from_xyz(
+                        0.0,
+                        0.0,
+                        PARALLAX_NEAREST_Z - (layers.0.len() - index) as f32,
+                    ),
+                ))
+                .set_parent(camera_id);
+        }
+    }
+
+    // Fits each quad to its slice of the view and scrolls its texture by the
+    // camera's position scaled by the layer's depth. Only what changed is
+    // written, since a mutably borrowed material is re-uploaded to the GPU.
+    pub fn scroll_parallax_layers(
+        layers: Res<ParallaxLayers>,
+        camera: Query<(&Camera, &Transform, &Projection), With<FollowCamera>>,
+        mut quads: Query<
+            (
+                &ParallaxLayerQuad,
+                &mut Transform,
+                &MeshMaterial3d<StandardMaterial>,
+            ),
+            Without<FollowCamera>,
+        >,
+        images: Res<Assets<Image>>,
+        mut materials: ResMut<Assets<StandardMaterial>>,
+    ) {
+        let Ok((camera, camera_t, projection)) = camera.get_single() else {
+            return;
+        };
+        let Some(viewport) = camera.logical_viewport_size() else {
+            return;
+        };
+        let scale = match projection {
+            Projection::Orthographic(ortho) => ortho.scale,
+            _ => 1.0,
+        };
+        let view = viewport * scale;
+
+        for (quad, mut quad_t, material) in quads.iter_mut() {
+            let Some(layer) = layers.0.get(quad.0) else {
+                continue;
+            };
+            let Some(current) = materials.get(material) else {
+                continue;
+            };
+            let Some(image) = current
+                .base_color_texture
+                .as_ref()
+                .and_then(|image| images.get(image))
+            else {
+                continue;
+            };
+
+            let size = Vec2::new(view.x, view.y * layer.height);
+            quad_t.set_if_neq(
+                Transform::from_xyz(
+                    0.0,
+                    view.y * (layer.bottom - 0.5) + size.y / 2.0,
+                    quad_t.translation.z,
+                )
+                .with_scale(size.extend(1.0)),
+            );
+
+            // Keep the image's aspect ratio as it's tiled across the layer
+            let tile_height = size.y / layer.vertical_tiles;
+            let tile_width = tile_height * image.aspect_ratio().ratio();
+            let repeats = Vec2::new(size.x / tile_width, layer.vertical_tiles);
+            let offset = Vec2::new(camera_t.translation.x * layer.depth / tile_width, 0.0);
+
+            let uv_transform = Affine2::from_scale_angle_translation(repeats, 0.0, offset);
+
+            if current.uv_transform != uv_transform {
+                if let Some(material) = materials.get_mut(material) {
+                    material.uv_transform = uv_transform;
+                }
+            }
+        }
+    }
+}
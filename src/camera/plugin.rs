@@ -6,9 +6,9 @@ use crate::GameState;
 
 use super::{
     events::{CameraPanEvent, CameraZoomEvent, CycleCameraModeEvent},
+    parallax::ParallaxLayers,
     systems::CameraState,
 };
-// use bevy_parallax::ParallaxSystems;
 
 pub struct CameraPlugin;
 
@@ -17,6 +17,7 @@ impl Plugin for CameraPlugin {
         app.add_plugins(InfiniteGridPlugin)
             .add_systems(Startup, CameraPlugin::setup_camera)
             .add_systems(Startup, CameraPlugin::setup_infinite_grid)
+            .add_systems(Update, CameraPlugin::spawn_parallax_layers)
             // .add_systems(Update, CameraPlugin::zoom_scale)
             .add_systems(
                 PostUpdate,
@@ -30,10 +31,19 @@ impl Plugin for CameraPlugin {
                         .before(TransformSystem::TransformPropagate)
                         .run_if(in_state(CameraState::Free)),
                     CameraPlugin::handle_zoom_event,
+                    CameraPlugin::fit_streaming_anchor_to_view
+                        .after(CameraPlugin::handle_zoom_event),
                     CameraPlugin::handle_cycle_camera_mode_event,
+                    // Whichever mode moved the camera, scroll to match
+                    CameraPlugin::scroll_parallax_layers
+                        .after(CameraPlugin::camera_follow)
+                        .after(CameraPlugin::free_camera)
+                        .after(CameraPlugin::handle_zoom_event)
+                        .before(TransformSystem::TransformPropagate),
                 )
                     .run_if(in_state(GameState::Ready)),
             )
+            .init_resource::<ParallaxLayers>()
             .init_state::<CameraState>()
             .add_event::<CameraPanEvent>()
             .add_event::<CameraZoomEvent>()
@@ -1,7 +1,13 @@
 use bevy::prelude::*;
 use bevy_infinite_grid::{InfiniteGridBundle, InfiniteGridSettings};
 
-use crate::bicycle::frame::BicycleFrame;
+use crate::{
+    bicycle::frame::BicycleFrame,
+    world::{
+        components::TerrainStreamingAnchor, plugin::WorldTerrainPlugin,
+        resources::MaxTerrainChunkCount,
+    },
+};
 
 use super::{
     components::FollowCamera,
@@ -46,6 +52,8 @@ impl CameraPlugin {
     pub fn setup_camera(mut commands: Commands) {
         commands.spawn((
             FollowCamera,
+            // Sized to the view once it's known, see fit_streaming_anchor_to_view
+            TerrainStreamingAnchor { radius: Some(1) },
             Camera3d::default(),
             Projection::Orthographic(OrthographicProjection::default_3d()),
         ));
@@ -96,4 +104,30 @@ impl CameraPlugin {
             }
         }
     }
+
+    // The camera only needs the chunks it can see, so it doesn't double the
+    // chunks streamed around the bicycle. Never more than the bicycle's own.
+    pub fn fit_streaming_anchor_to_view(
+        mut camera: Query<(&Camera, &Projection, &mut TerrainStreamingAnchor), With<FollowCamera>>,
+        terrain_chunk_count: Res<MaxTerrainChunkCount>,
+    ) {
+        let Ok((camera, projection, mut anchor)) = camera.get_single_mut() else {
+            return;
+        };
+        let Some(viewport) = camera.logical_viewport_size() else {
+            return;
+        };
+        let scale = match projection {
+            Projection::Orthographic(ortho) => ortho.scale,
+            _ => 1.0,
+        };
+
+        let half_width = viewport.x * scale / 2.0;
+        let radius = (half_width / WorldTerrainPlugin::CHUNK_WIDTH).ceil() as u8;
+        let radius = Some(radius.clamp(1, (terrain_chunk_count.0 / 2).max(1)));
+
+        if anchor.radius != radius {
+            anchor.radius = radius;
+        }
+    }
 }
@@ -0,0 +1,5 @@
+use bevy::prelude::*;
+
+// Parent of the start gate and finish line of the active course
+#[derive(Component)]
+pub struct CourseMarkers;
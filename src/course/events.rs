@@ -0,0 +1,19 @@
+use bevy::prelude::Event;
+
+use super::resources::RunStats;
+
+// Sent when the bicycle crosses the start gate
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct RunStarted;
+
+// Sent when the bicycle crosses the finish line of a run it started at the gate
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct RunFinished {
+    // Seconds from crossing the start gate to crossing the finish line
+    pub elapsed: f64,
+    // Seconds from crossing the start gate to each split
+    pub splits: Vec<f64>,
+    // Seconds from crossing the start gate to each PROGRESS_INTERVAL along the course
+    pub progress: Vec<f64>,
+    pub stats: RunStats,
+}
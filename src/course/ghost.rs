@@ -0,0 +1,467 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bicycle::{
+        frame::{BicycleFrame, FrameGeometry},
+        wheel::BicycleWheel,
+    },
+    PNGAssets,
+};
+
+use super::{
+    events::{RunFinished, RunStarted},
+    plugin::CoursePlugin,
+    records::RecordsError,
+    resources::{CourseRun, TimeTrial},
+};
+
+pub const GHOST_DIRECTORY: &str = "records/ghosts";
+// Physics steps between recorded poses, about 20 a second at the default 64 Hz
+const GHOST_SAMPLE_STEPS: u32 = 3;
+// Just behind the bicycle, so it never hides it
+const GHOST_Z: f32 = -1.0;
+const GHOST_ALPHA: f32 = 0.4;
+const GHOST_TUBE_WIDTH: f32 = 2.0;
+// The same tubes as the frame's collider
+const GHOST_TUBES: [(FrameGeometry, FrameGeometry); 5] = [
+    (FrameGeometry::RearHub, FrameGeometry::BottomBracket),
+    (FrameGeometry::BottomBracket, FrameGeometry::StemClamp),
+    (FrameGeometry::SeatClamp, FrameGeometry::RearHub),
+    (FrameGeometry::SeatClamp, FrameGeometry::StemClamp),
+    (FrameGeometry::StemClamp, FrameGeometry::FrontHub),
+];
+
+// A see-through copy of one of the bicycle's bodies. It has no rigid body or
+// collider, so it passes through everything.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GhostPart {
+    Frame,
+    FrontWheel,
+    BackWheel,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BodyPose {
+    pub x: f64,
+    pub y: f64,
+    pub angle: f64,
+}
+
+impl BodyPose {
+    fn new(position: &Position, rotation: &Rotation) -> Self {
+        BodyPose {
+            x: position.x,
+            y: position.y,
+            angle: rotation.as_radians(),
+        }
+    }
+
+    fn lerp(&self, other: &BodyPose, t: f64) -> Self {
+        // Take the short way round, so a wheel doesn't spin back a full turn
+        let turn = (other.angle - self.angle + std::f64::consts::PI)
+            .rem_euclid(std::f64::consts::TAU)
+            - std::f64::consts::PI;
+
+        BodyPose {
+            x: self.x + (other.x - self.x) * t,
+            y: self.y + (other.y - self.y) * t,
+            angle: self.angle + turn * t,
+        }
+    }
+
+    fn transform(&self) -> Transform {
+        Transform::from_xyz(self.x as f32, self.y as f32, GHOST_Z)
+            .with_rotation(Quat::from_rotation_z(self.angle as f32))
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GhostSample {
+    // Seconds since the start gate
+    pub time: f64,
+    pub frame: BodyPose,
+    pub front_wheel: BodyPose,
+    pub back_wheel: BodyPose,
+}
+
+impl GhostSample {
+    fn pose(&self, part: GhostPart) -> BodyPose {
+        match part {
+            GhostPart::Frame => self.frame,
+            GhostPart::FrontWheel => self.front_wheel,
+            GhostPart::BackWheel => self.back_wheel,
+        }
+    }
+}
+
+// The bicycle's pose through a run, sampled at a fixed interval
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ghost {
+    pub elapsed: f64,
+    pub samples: Vec<GhostSample>,
+}
+
+impl Ghost {
+    // One ghost is kept for each course and bike setup, like personal bests
+    pub fn path(course_id: &str, bike_id: &str) -> PathBuf {
+        let name: String = format!("{} {}", course_id, bike_id)
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        Path::new(GHOST_DIRECTORY).join(format!("{}.ron", name))
+    }
+
+    // None if no ghost has been saved yet
+    pub fn load(path: &Path) -> Result<Option<Self>, RecordsError> {
+        match fs::read_to_string(path) {
+            Ok(text) => ron::from_str(&text).map(Some).map_err(RecordsError::Parse),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), RecordsError> {
+        let text = ron::ser::to_string_pretty(self, PrettyConfig::default().compact_arrays(true))
+            .map_err(RecordsError::Serialize)?;
+
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, text)?;
+
+        Ok(())
+    }
+
+    // Pose between the samples either side of `time`, or None outside the run
+    pub fn pose_at(&self, time: f64) -> Option<GhostSample> {
+        if time < 0.0 || time > self.elapsed {
+            return None;
+        }
+
+        let next = self.samples.partition_point(|sample| sample.time <= time);
+        let (previous, next) = match (
+            next.checked_sub(1).and_then(|i| self.samples.get(i)),
+            self.samples.get(next),
+        ) {
+            (Some(previous), Some(next)) => (previous, next),
+            (Some(sample), None) | (None, Some(sample)) => return Some(*sample),
+            (None, None) => return None,
+        };
+
+        let t = (time - previous.time) / (next.time - previous.time).max(f64::EPSILON);
+        Some(GhostSample {
+            time,
+            frame: previous.frame.lerp(&next.frame, t),
+            front_wheel: previous.front_wheel.lerp(&next.front_wheel, t),
+            back_wheel: previous.back_wheel.lerp(&next.back_wheel, t),
+        })
+    }
+}
+
+// Records the current run, and replays the best run for the course and bike
+// setup alongside it
+#[derive(Resource)]
+pub struct GhostReplay {
+    pub enabled: bool,
+    recording: Ghost,
+    // Physics steps since the last recorded pose
+    steps: u32,
+    pub replaying: Option<Ghost>,
+    pub error: Option<String>,
+}
+
+impl Default for GhostReplay {
+    fn default() -> Self {
+        GhostReplay {
+            enabled: true,
+            recording: Ghost::default(),
+            steps: 0,
+            replaying: None,
+            error: None,
+        }
+    }
+}
+
+impl CoursePlugin {
+    // Runs after the time trial records are updated, so a finished run knows
+    // whether it set a new personal best
+    pub fn update_ghost_replay(
+        mut started_events: EventReader<RunStarted>,
+        mut finished_events: EventReader<RunFinished>,
+        mut ghost_replay: ResMut<GhostReplay>,
+        time_trial: Res<TimeTrial>,
+    ) {
+        let ghost_replay = ghost_replay.as_mut();
+        let path = time_trial
+            .course_id
+            .as_ref()
+            .filter(|_| time_trial.enabled)
+            .map(|course_id| Ghost::path(course_id, &time_trial.bike_id));
+
+        if started_events.read().count() > 0 {
+            ghost_replay.recording = Ghost::default();
+            ghost_replay.steps = 0;
+            ghost_replay.replaying = None;
+
+            if let Some(path) = path.as_ref().filter(|_| ghost_replay.enabled) {
+                match Ghost::load(path) {
+                    Ok(ghost) => ghost_replay.replaying = ghost,
+                    Err(err) => {
+                        error!("Failed to load ghost {:?}: {}", path, err);
+                        ghost_replay.error = Some(format!("{}", err));
+                    }
+                }
+            }
+        }
+
+        for finished in finished_events.read() {
+            let Some(path) = path
+                .as_ref()
+                .filter(|_| time_trial.new_best)
+                // Without the records a better ghost might be saved over
+                .filter(|_| time_trial.loaded)
+            else {
+                continue;
+            };
+
+            ghost_replay.recording.elapsed = finished.elapsed;
+            match ghost_replay.recording.save(path) {
+                Ok(()) => {
+                    info!("Saved ghost to {:?}", path);
+                    ghost_replay.error = None;
+                }
+                Err(err) => {
+                    error!("Failed to save ghost {:?}: {}", path, err);
+                    ghost_replay.error = Some(format!("{}", err));
+                }
+            }
+        }
+    }
+
+    // Runs after each physics step, so poses are taken at fixed intervals of
+    // the physics clock rather than whenever a frame is drawn
+    pub fn record_ghost(
+        mut ghost_replay: ResMut<GhostReplay>,
+        run: Res<CourseRun>,
+        frame: Query<(&Position, &Rotation), With<BicycleFrame>>,
+        wheels: Query<(&BicycleWheel, &Position, &Rotation)>,
+        time: Res<Time>,
+    ) {
+        let Some(elapsed) = run
+            .elapsed(time.elapsed_secs_f64())
+            .filter(|_| run.is_running())
+        else {
+            return;
+        };
+        let Ok((position, rotation)) = frame.get_single() else {
+            return;
+        };
+
+        let ghost_replay = ghost_replay.as_mut();
+        ghost_replay.steps += 1;
+        if !ghost_replay.recording.samples.is_empty() && ghost_replay.steps < GHOST_SAMPLE_STEPS {
+            return;
+        }
+        ghost_replay.steps = 0;
+
+        let mut sample = GhostSample {
+            // The physics clock can be a little behind the start gate crossing
+            time: elapsed.max(0.0),
+            frame: BodyPose::new(position, rotation),
+            ..default()
+        };
+        for (wheel, position, rotation) in wheels.iter() {
+            match wheel {
+                BicycleWheel::Front => sample.front_wheel = BodyPose::new(position, rotation),
+                BicycleWheel::Back => sample.back_wheel = BodyPose::new(position, rotation),
+            }
+        }
+        ghost_replay.recording.samples.push(sample);
+    }
+
+    pub fn spawn_ghost_bike(
+        mut commands: Commands,
+        png_assets: Res<PNGAssets>,
+        mut meshes: ResMut<Assets<Mesh>>,
+        mut materials: ResMut<Assets<StandardMaterial>>,
+    ) {
+        let color = Color::srgba(1.0, 1.0, 1.0, GHOST_ALPHA);
+        let tube_material = materials.add(StandardMaterial {
+            base_color: color,
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        });
+        let wheel_material = materials.add(StandardMaterial {
+            base_color: color,
+            base_color_texture: png_assets.assets.get("bicycle_wheel").cloned(),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        });
+
+        let geometry = BicycleFrame::new().geometry;
+        commands
+            .spawn((
+                GhostPart::Frame,
+                Name::new("Ghost Frame"),
+                Transform::default(),
+                Visibility::Hidden,
+            ))
+            .with_children(|frame| {
+                for (from, to) in GHOST_TUBES {
+                    let (from, to) = (geometry[&from].as_vec2(), geometry[&to].as_vec2());
+                    frame.spawn((
+                        Mesh3d(meshes.add(Rectangle::new(from.distance(to), GHOST_TUBE_WIDTH))),
+                        MeshMaterial3d(tube_material.clone()),
+                        Transform::from_translation(((from + to) / 2.0).extend(0.0))
+                            .with_rotation(Quat::from_rotation_z((to - from).to_angle())),
+                    ));
+                }
+            });
+
+        // The same size as the wheel sprites
+        let diameter = BicycleWheel::size() * 2.0;
+        let wheel_mesh = meshes.add(Rectangle::new(diameter, diameter));
+        for (part, name) in [
+            (GhostPart::FrontWheel, "Ghost Front Wheel"),
+            (GhostPart::BackWheel, "Ghost Back Wheel"),
+        ] {
+            commands.spawn((
+                part,
+                Name::new(name),
+                Mesh3d(wheel_mesh.clone()),
+                MeshMaterial3d(wheel_material.clone()),
+                Transform::default(),
+                Visibility::Hidden,
+            ));
+        }
+    }
+
+    // Keeps the ghost in time with the current run, and hidden without one
+    pub fn place_ghost_bike(
+        ghost_replay: Res<GhostReplay>,
+        run: Res<CourseRun>,
+        mut parts: Query<(&GhostPart, &mut Transform, &mut Visibility)>,
+        time: Res<Time>,
+    ) {
+        let sample = ghost_replay
+            .replaying
+            .as_ref()
+            .filter(|_| ghost_replay.enabled)
+            .zip(run.elapsed(time.elapsed_secs_f64()))
+            .and_then(|(ghost, elapsed)| {
+                // Once the rider finishes too, the ghost waits at its finish
+                let elapsed = run
+                    .result
+                    .as_ref()
+                    .map(|result| result.elapsed)
+                    .unwrap_or(elapsed);
+                ghost.pose_at(elapsed.min(ghost.elapsed))
+            });
+
+        for (part, mut transform, mut visibility) in parts.iter_mut() {
+            match sample {
+                Some(sample) => {
+                    *transform = sample.pose(*part).transform();
+                    visibility.set_if_neq(Visibility::Inherited);
+                }
+                None => {
+                    visibility.set_if_neq(Visibility::Hidden);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{f64::consts::PI, path::Path};
+
+    use super::{BodyPose, Ghost, GhostSample, GHOST_DIRECTORY};
+
+    fn pose(x: f64, angle: f64) -> BodyPose {
+        BodyPose { x, y: 0.0, angle }
+    }
+
+    fn sample(time: f64, x: f64) -> GhostSample {
+        GhostSample {
+            time,
+            frame: pose(x, 0.0),
+            ..Default::default()
+        }
+    }
+
+    // Samples at 0.5s and 1.5s of a two second run
+    fn ghost() -> Ghost {
+        Ghost {
+            elapsed: 2.0,
+            samples: vec![sample(0.5, 10.0), sample(1.5, 30.0)],
+        }
+    }
+
+    #[test]
+    fn holds_the_first_sample_before_it() {
+        assert_eq!(ghost().pose_at(0.0).unwrap().frame, pose(10.0, 0.0));
+        assert_eq!(ghost().pose_at(-0.1), None);
+    }
+
+    #[test]
+    fn interpolates_between_samples() {
+        let between = ghost().pose_at(0.75).unwrap();
+
+        assert_eq!(between.time, 0.75);
+        assert_eq!(between.frame, pose(15.0, 0.0));
+    }
+
+    #[test]
+    fn holds_the_last_sample_after_it() {
+        assert_eq!(ghost().pose_at(2.0).unwrap().frame, pose(30.0, 0.0));
+        assert_eq!(ghost().pose_at(2.1), None);
+    }
+
+    #[test]
+    fn has_no_pose_without_samples() {
+        let ghost = Ghost {
+            elapsed: 2.0,
+            samples: vec![],
+        };
+
+        assert_eq!(ghost.pose_at(1.0), None);
+    }
+
+    #[test]
+    fn lerp_turns_the_short_way_across_pi() {
+        let from = pose(0.0, PI - 0.1);
+        let to = pose(10.0, -PI + 0.1);
+
+        let halfway = from.lerp(&to, 0.5);
+        assert_eq!(halfway.x, 5.0);
+        assert!((halfway.angle - PI).abs() < 1e-9, "{}", halfway.angle);
+
+        let back = to.lerp(&from, 0.5);
+        assert!((back.angle + PI).abs() < 1e-9, "{}", back.angle);
+    }
+
+    #[test]
+    fn path_is_a_safe_file_name() {
+        let path = Ghost::path(
+            "track \"Hills\" #1a2b, 500 m",
+            "chainring 4.0 / cassette 2.0",
+        );
+
+        assert_eq!(
+            path,
+            Path::new(GHOST_DIRECTORY)
+                .join("track__Hills___1a2b__500_m_chainring_4_0___cassette_2_0.ron")
+        );
+    }
+}
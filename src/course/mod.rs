@@ -0,0 +1,7 @@
+pub mod components;
+pub mod events;
+pub mod ghost;
+pub mod plugin;
+pub mod records;
+pub mod resources;
+pub mod systems;
@@ -0,0 +1,55 @@
+use avian2d::prelude::PhysicsSet;
+use bevy::prelude::*;
+
+use crate::{track::plugin::TrackPlugin, world::plugin::WorldTerrainPlugin, GameState};
+
+use super::{
+    events::{RunFinished, RunStarted},
+    ghost::GhostReplay,
+    resources::{ActiveCourse, CourseRun, CourseSettings, TimeTrial},
+};
+
+pub struct CoursePlugin;
+
+impl Plugin for CoursePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CourseSettings>()
+            .init_resource::<ActiveCourse>()
+            .init_resource::<CourseRun>()
+            .init_resource::<TimeTrial>()
+            .init_resource::<GhostReplay>()
+            .add_event::<RunStarted>()
+            .add_event::<RunFinished>()
+            .add_systems(Startup, CoursePlugin::load_time_trial_records)
+            // The wheel texture has to be loaded first
+            .add_systems(OnEnter(GameState::Ready), CoursePlugin::spawn_ghost_bike)
+            .add_systems(
+                FixedPostUpdate,
+                CoursePlugin::record_ghost
+                    .after(PhysicsSet::StepSimulation)
+                    .run_if(in_state(GameState::Ready)),
+            )
+            .add_systems(
+                Update,
+                (
+                    CoursePlugin::update_active_course
+                        .after(TrackPlugin::handle_track_event)
+                        .before(WorldTerrainPlugin::rebuild_terrain_generator),
+                    CoursePlugin::spawn_course_markers
+                        .after(WorldTerrainPlugin::rebuild_terrain_generator),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    CoursePlugin::identify_time_trial,
+                    CoursePlugin::time_course_run,
+                    CoursePlugin::record_time_trial_results,
+                    CoursePlugin::update_ghost_replay,
+                    CoursePlugin::place_ghost_bike,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Ready)),
+            );
+    }
+}
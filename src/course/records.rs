@@ -0,0 +1,177 @@
+use std::{collections::BTreeMap, error::Error, fmt, fs, io, path::Path};
+
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use super::events::RunFinished;
+
+pub const RECORDS_PATH: &str = "records/time_trials.ron";
+
+#[derive(Debug)]
+pub enum RecordsError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+}
+
+impl fmt::Display for RecordsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordsError::Io(err) => write!(f, "{}", err),
+            RecordsError::Parse(err) => write!(f, "{}", err),
+            RecordsError::Serialize(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for RecordsError {}
+
+impl From<io::Error> for RecordsError {
+    fn from(err: io::Error) -> Self {
+        RecordsError::Io(err)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeTrialResult {
+    pub elapsed: f64,
+    pub splits: Vec<f64>,
+    // Missing from records saved before it was timed
+    #[serde(default)]
+    pub progress: Vec<f64>,
+    pub distance: f64,
+    pub max_speed: f64,
+}
+
+impl From<&RunFinished> for TimeTrialResult {
+    fn from(run: &RunFinished) -> Self {
+        TimeTrialResult {
+            elapsed: run.elapsed,
+            splits: run.splits.clone(),
+            progress: run.progress.clone(),
+            distance: run.stats.odometer.distance,
+            max_speed: run.stats.max_speed,
+        }
+    }
+}
+
+// Every finished time trial, by course and then bike setup
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeTrialRecords {
+    pub courses: BTreeMap<String, BTreeMap<String, Vec<TimeTrialResult>>>,
+}
+
+impl TimeTrialRecords {
+    // No file yet just means no records
+    pub fn load(path: &Path) -> Result<Self, RecordsError> {
+        match fs::read_to_string(path) {
+            Ok(text) => ron::from_str(&text).map_err(RecordsError::Parse),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(TimeTrialRecords::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), RecordsError> {
+        let text = ron::ser::to_string_pretty(self, PrettyConfig::default())
+            .map_err(RecordsError::Serialize)?;
+
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, text)?;
+
+        Ok(())
+    }
+
+    pub fn results(&self, course: &str, bike: &str) -> &[TimeTrialResult] {
+        self.courses
+            .get(course)
+            .and_then(|bikes| bikes.get(bike))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn personal_best(&self, course: &str, bike: &str) -> Option<&TimeTrialResult> {
+        self.results(course, bike)
+            .iter()
+            .min_by(|a, b| a.elapsed.total_cmp(&b.elapsed))
+    }
+
+    // Whether the result beats the personal best it's added alongside
+    pub fn add(&mut self, course: &str, bike: &str, result: TimeTrialResult) -> bool {
+        let is_best = self
+            .personal_best(course, bike)
+            .is_none_or(|best| result.elapsed < best.elapsed);
+
+        self.courses
+            .entry(course.to_string())
+            .or_default()
+            .entry(bike.to_string())
+            .or_default()
+            .push(result);
+
+        is_best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TimeTrialRecords, TimeTrialResult};
+
+    fn result(elapsed: f64) -> TimeTrialResult {
+        TimeTrialResult {
+            elapsed,
+            splits: vec![],
+            progress: vec![],
+            distance: 1000.0,
+            max_speed: 100.0,
+        }
+    }
+
+    #[test]
+    fn first_result_is_a_personal_best() {
+        let mut records = TimeTrialRecords::default();
+
+        assert!(records.add("course", "bike", result(30.0)));
+        assert_eq!(records.personal_best("course", "bike"), Some(&result(30.0)));
+    }
+
+    #[test]
+    fn personal_best_is_the_fastest_result() {
+        let mut records = TimeTrialRecords::default();
+        records.add("course", "bike", result(30.0));
+
+        assert!(!records.add("course", "bike", result(35.0)));
+        assert!(records.add("course", "bike", result(25.0)));
+        assert_eq!(records.results("course", "bike").len(), 3);
+        assert_eq!(records.personal_best("course", "bike"), Some(&result(25.0)));
+    }
+
+    #[test]
+    fn tying_the_personal_best_does_not_beat_it() {
+        let mut records = TimeTrialRecords::default();
+        records.add("course", "bike", result(30.0));
+
+        assert!(!records.add("course", "bike", result(30.0)));
+    }
+
+    #[test]
+    fn records_are_kept_apart_by_course_and_bike() {
+        let mut records = TimeTrialRecords::default();
+        records.add("course", "bike", result(30.0));
+
+        assert!(records.add("course", "other bike", result(40.0)));
+        assert!(records.add("other course", "bike", result(40.0)));
+        assert_eq!(records.personal_best("course", "bike"), Some(&result(30.0)));
+        assert_eq!(records.personal_best("missing", "bike"), None);
+    }
+
+    #[test]
+    fn results_saved_without_progress_still_load() {
+        let text = "(elapsed: 30.0, splits: [10.0, 20.0], distance: 1000.0, max_speed: 100.0)";
+        let loaded: TimeTrialResult = ron::from_str(text).unwrap();
+
+        assert_eq!(loaded.splits, vec![10.0, 20.0]);
+        assert!(loaded.progress.is_empty());
+    }
+}
@@ -0,0 +1,231 @@
+use std::path::PathBuf;
+
+use bevy::{math::DVec2, prelude::Resource};
+use serde::{Deserialize, Serialize};
+
+use crate::{bicycle::trip::Odometer, world::elevation_profile::UNITS_PER_METER};
+
+use super::{
+    events::RunFinished,
+    records::{TimeTrialRecords, TimeTrialResult, RECORDS_PATH},
+};
+
+// Ground left either side of the course before the walls
+const COURSE_RUNOUT: f64 = 50.0 * UNITS_PER_METER;
+// Procedural courses start a little ahead of where the bicycle first spawns
+const PROCEDURAL_COURSE_START: f64 = 20.0 * UNITS_PER_METER;
+// Courses without checkpoints are split into this many equal sections
+const DEFAULT_SECTIONS: usize = 4;
+// Runs are timed at every stretch this long past the start gate, so they can
+// be compared between splits
+pub const PROGRESS_INTERVAL: f64 = 10.0 * UNITS_PER_METER;
+
+// Start gate and finish line, in world x
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Course {
+    pub start: f64,
+    pub finish: f64,
+}
+
+impl Course {
+    pub fn length(&self) -> f64 {
+        self.finish - self.start
+    }
+
+    // Where the terrain ends in walls
+    pub fn bounds(&self) -> (f64, f64) {
+        (self.start - COURSE_RUNOUT, self.finish + COURSE_RUNOUT)
+    }
+
+    // Where split times are taken: the checkpoints between the start gate and
+    // the finish line, or evenly along the course if there are none
+    pub fn split_points(&self, checkpoints: &[f64]) -> Vec<f64> {
+        let inside: Vec<f64> = checkpoints
+            .iter()
+            .copied()
+            .filter(|x| *x > self.start && *x < self.finish)
+            .collect();
+        if !inside.is_empty() {
+            return inside;
+        }
+
+        (1..DEFAULT_SECTIONS)
+            .map(|section| self.start + self.length() * section as f64 / DEFAULT_SECTIONS as f64)
+            .collect()
+    }
+}
+
+// Course used when no track supplies one
+#[derive(Resource, Clone, PartialEq)]
+pub struct CourseSettings {
+    pub procedural: bool,
+    // Meters from the start gate to the finish line
+    pub length: f64,
+}
+
+impl Default for CourseSettings {
+    fn default() -> Self {
+        CourseSettings {
+            procedural: false,
+            length: 500.0,
+        }
+    }
+}
+
+impl CourseSettings {
+    pub fn course(&self) -> Option<Course> {
+        self.procedural.then(|| Course {
+            start: PROCEDURAL_COURSE_START,
+            finish: PROCEDURAL_COURSE_START + self.length * UNITS_PER_METER,
+        })
+    }
+}
+
+// The course being ridden, if any. The terrain is walled in around it.
+#[derive(Resource, Default, PartialEq)]
+pub struct ActiveCourse {
+    pub course: Option<Course>,
+    // In the order they're reached
+    pub splits: Vec<f64>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct RunStats {
+    // Counted the same way as the trip computer's
+    pub odometer: Odometer,
+    pub max_speed: f64,
+    pub checkpoints: usize,
+}
+
+impl RunStats {
+    pub fn average_speed(&self, elapsed: f64) -> f64 {
+        if elapsed > 0.0 {
+            self.odometer.distance / elapsed
+        } else {
+            0.0
+        }
+    }
+}
+
+// Progress through the active course. A run starts crossing the start gate
+// and ends crossing the finish line, or riding back behind the gate.
+#[derive(Resource, Default)]
+pub struct CourseRun {
+    pub(super) last_position: Option<DVec2>,
+    // Time::elapsed the start gate was crossed
+    pub started_at: Option<f64>,
+    pub stats: RunStats,
+    // Seconds from the start gate to each split reached so far
+    pub splits: Vec<f64>,
+    // Seconds from the start gate to each PROGRESS_INTERVAL reached so far
+    pub progress: Vec<f64>,
+    pub result: Option<RunFinished>,
+}
+
+impl CourseRun {
+    pub fn elapsed(&self, now: f64) -> Option<f64> {
+        self.started_at.map(|started_at| now - started_at)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started_at.is_some() && self.result.is_none()
+    }
+
+    // Latest progress time and the comparison's at the same point, if it got there
+    pub fn progress_against(&self, comparison: &TimeTrialResult) -> Option<(f64, f64)> {
+        let index = self.progress.len().checked_sub(1)?;
+        Some((self.progress[index], *comparison.progress.get(index)?))
+    }
+}
+
+// Time trial mode: runs are split, compared against the personal best and
+// saved, per course and bike setup
+#[derive(Resource)]
+pub struct TimeTrial {
+    pub enabled: bool,
+    pub path: PathBuf,
+    pub records: TimeTrialRecords,
+    // Records that failed to load are never saved over
+    pub(super) loaded: bool,
+    // Names the records are filed under, None without a course
+    pub course_id: Option<String>,
+    pub bike_id: String,
+    // Personal best when the current run started, which it's compared against
+    pub comparison: Option<TimeTrialResult>,
+    // Whether the last run set a new personal best
+    pub new_best: bool,
+    pub error: Option<String>,
+}
+
+impl Default for TimeTrial {
+    fn default() -> Self {
+        TimeTrial {
+            enabled: false,
+            path: PathBuf::from(RECORDS_PATH),
+            records: TimeTrialRecords::default(),
+            loaded: false,
+            course_id: None,
+            bike_id: String::new(),
+            comparison: None,
+            new_best: false,
+            error: None,
+        }
+    }
+}
+
+impl TimeTrial {
+    pub fn personal_best(&self) -> Option<&TimeTrialResult> {
+        let course_id = self.course_id.as_ref()?;
+        self.records.personal_best(course_id, &self.bike_id)
+    }
+
+    pub fn attempts(&self) -> usize {
+        self.course_id
+            .as_ref()
+            .map(|course_id| self.records.results(course_id, &self.bike_id).len())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Course, CourseRun, TimeTrialResult};
+
+    const COURSE: Course = Course {
+        start: 100.0,
+        finish: 900.0,
+    };
+
+    #[test]
+    fn splits_at_checkpoints_inside_the_course() {
+        let checkpoints = [50.0, 100.0, 300.0, 600.0, 900.0, 1200.0];
+
+        assert_eq!(COURSE.split_points(&checkpoints), vec![300.0, 600.0]);
+    }
+
+    #[test]
+    fn splits_into_equal_sections_without_checkpoints_inside() {
+        for checkpoints in [&[][..], &[50.0, 1200.0][..]] {
+            assert_eq!(COURSE.split_points(checkpoints), vec![300.0, 500.0, 700.0]);
+        }
+    }
+
+    #[test]
+    fn progress_is_compared_at_the_same_point() {
+        let comparison = TimeTrialResult {
+            elapsed: 30.0,
+            splits: vec![],
+            progress: vec![1.0, 2.0, 3.0],
+            distance: 800.0,
+            max_speed: 100.0,
+        };
+        let mut run = CourseRun::default();
+        assert_eq!(run.progress_against(&comparison), None);
+
+        run.progress = vec![1.5, 2.5];
+        assert_eq!(run.progress_against(&comparison), Some((2.5, 2.0)));
+
+        run.progress = vec![1.0, 2.0, 3.0, 4.0];
+        assert_eq!(run.progress_against(&comparison), None);
+    }
+}
@@ -0,0 +1,395 @@
+use std::fmt::Debug;
+
+use avian2d::prelude::*;
+use bevy::{
+    color::palettes::css::{BLACK, LIME, WHITE},
+    prelude::*,
+};
+
+use crate::{
+    bicycle::{
+        frame::BicycleFrame,
+        groupset::{CassetteRadius, ChainringRadius},
+    },
+    track::{events::CheckpointReachedEvent, resources::ActiveTrack},
+    world::{
+        elevation_profile::UNITS_PER_METER,
+        resources::{ActiveTerrainGenerator, ElevationProfileSource, TerrainSeed, TerrainSettings},
+        terrain_query::TerrainSample,
+    },
+};
+
+use super::{
+    components::CourseMarkers,
+    events::{RunFinished, RunStarted},
+    plugin::CoursePlugin,
+    records::TimeTrialRecords,
+    resources::{ActiveCourse, CourseRun, CourseSettings, TimeTrial, PROGRESS_INTERVAL},
+};
+
+const GATE_WIDTH: f32 = 8.0;
+const GATE_HEIGHT: f32 = 400.0;
+// Squares down each side of the finish line
+const FINISH_SQUARE: f32 = GATE_WIDTH / 2.0;
+// In front of the terrain chunks, with the track props
+const MARKER_Z: f32 = 11.0;
+
+// FNV-1a, which unlike std's hasher is the same from one build to the next
+const FNV_OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+// Short fingerprint of everything that shapes the terrain, so changing any of
+// it files times under a different course
+fn fingerprint(value: &impl Debug) -> String {
+    let hash = format!("{:?}", value)
+        .bytes()
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        });
+    format!("{:08x}", (hash ^ (hash >> 32)) as u32)
+}
+
+// Fraction of the last frame's movement from `from` to `to` taken to reach `line`
+fn crossing_fraction(from: f64, to: f64, line: f64) -> f64 {
+    ((line - from) / (to - from)).clamp(0.0, 1.0)
+}
+
+impl CoursePlugin {
+    // A track's own course takes priority over the procedural one
+    pub fn update_active_course(
+        active_track: Res<ActiveTrack>,
+        settings: Res<CourseSettings>,
+        mut active_course: ResMut<ActiveCourse>,
+        mut run: ResMut<CourseRun>,
+    ) {
+        if !(active_track.is_changed() || settings.is_changed()) {
+            return;
+        }
+
+        let (course, checkpoints) = match &active_track.track {
+            Some(track) => (track.course, track.checkpoints()),
+            None => (settings.course(), vec![]),
+        };
+        let splits = course
+            .map(|course| course.split_points(&checkpoints))
+            .unwrap_or_default();
+
+        // Only a new course walls the terrain in again, and ends the current run
+        if active_course.set_if_neq(ActiveCourse { course, splits }) {
+            *run = CourseRun::default();
+        }
+    }
+
+    pub fn spawn_course_markers(
+        mut commands: Commands,
+        active_course: Res<ActiveCourse>,
+        terrain_generator: Res<ActiveTerrainGenerator>,
+        markers: Query<Entity, With<CourseMarkers>>,
+        mut meshes: ResMut<Assets<Mesh>>,
+        mut materials: ResMut<Assets<StandardMaterial>>,
+    ) {
+        // The generator changes with the seed too, which moves the ground under the markers
+        if !(active_course.is_changed() || terrain_generator.is_changed()) {
+            return;
+        }
+
+        for markers in markers.iter() {
+            commands.entity(markers).despawn_recursive();
+        }
+
+        let Some(course) = active_course.course else {
+            return;
+        };
+        let ground = |x: f64| TerrainSample::at(terrain_generator.0.as_ref(), x).height as f32;
+
+        let mut material = |color: Srgba| {
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: color.into(),
+                unlit: true,
+                ..Default::default()
+            }))
+        };
+        let gate_material = material(LIME);
+        let square_materials = [material(WHITE), material(BLACK)];
+        let square_mesh = meshes.add(Rectangle::new(FINISH_SQUARE, FINISH_SQUARE));
+
+        commands
+            .spawn((
+                CourseMarkers,
+                Name::new("Course Markers"),
+                Transform::default(),
+                Visibility::default(),
+            ))
+            .with_children(|markers| {
+                markers.spawn((
+                    Name::new("Start Gate"),
+                    Mesh3d(meshes.add(Rectangle::new(GATE_WIDTH, GATE_HEIGHT))),
+                    gate_material,
+                    Transform::from_xyz(
+                        course.start as f32,
+                        ground(course.start) + GATE_HEIGHT / 2.0,
+                        MARKER_Z,
+                    ),
+                ));
+
+                // Chequered, two squares across
+                markers
+                    .spawn((
+                        Name::new("Finish Line"),
+                        Transform::from_xyz(course.finish as f32, ground(course.finish), MARKER_Z),
+                        Visibility::default(),
+                    ))
+                    .with_children(|finish| {
+                        let rows = (GATE_HEIGHT / FINISH_SQUARE) as usize;
+                        for row in 0..rows {
+                            for column in 0..2 {
+                                finish.spawn((
+                                    Mesh3d(square_mesh.clone()),
+                                    square_materials[(row + column) % 2].clone(),
+                                    Transform::from_xyz(
+                                        (column as f32 - 0.5) * FINISH_SQUARE,
+                                        (row as f32 + 0.5) * FINISH_SQUARE,
+                                        0.0,
+                                    ),
+                                ));
+                            }
+                        }
+                    });
+            });
+    }
+
+    // Times the bicycle from the start gate to the finish line. Crossing times
+    // are interpolated within the frame, so they don't depend on the frame rate.
+    pub fn time_course_run(
+        active_course: Res<ActiveCourse>,
+        mut run: ResMut<CourseRun>,
+        frame: Query<(Ref<BicycleFrame>, &Position, &LinearVelocity)>,
+        mut checkpoint_events: EventReader<CheckpointReachedEvent>,
+        mut started_events: EventWriter<RunStarted>,
+        mut finished_events: EventWriter<RunFinished>,
+        time: Res<Time>,
+    ) {
+        let checkpoints = checkpoint_events.read().count();
+
+        let Some(course) = active_course.course else {
+            return;
+        };
+        let Ok((frame, position, lin_vel)) = frame.get_single() else {
+            return;
+        };
+
+        // A respawned bicycle has to come through the gate again
+        if frame.is_added() {
+            *run = CourseRun::default();
+        }
+
+        let now = time.elapsed_secs_f64();
+        let dt = time.delta_secs_f64();
+        let Some(last_position) = run.last_position.replace(position.0) else {
+            return;
+        };
+        let (last_x, x) = (last_position.x, position.x);
+
+        let Some(started_at) = run.started_at else {
+            if last_x < course.start && x >= course.start {
+                let fraction = crossing_fraction(last_x, x, course.start);
+                run.started_at = Some(now - dt * (1.0 - fraction));
+                run.stats.odometer.update(position.0);
+                info!("Run started");
+                started_events.send(RunStarted);
+            }
+            return;
+        };
+
+        // Riding back behind the gate abandons the run, or sets up another
+        // after finishing
+        if x < course.start {
+            if run.result.is_none() {
+                info!("Run abandoned behind the start gate");
+            }
+            *run = CourseRun {
+                last_position: Some(position.0),
+                ..default()
+            };
+            return;
+        }
+        if run.result.is_some() {
+            return;
+        }
+
+        run.stats.odometer.update(position.0);
+        run.stats.max_speed = run.stats.max_speed.max(lin_vel.length());
+        run.stats.checkpoints += checkpoints;
+
+        // Time passed when the bicycle crossed `line` during the last frame
+        let crossed = |line: f64| {
+            (last_x < line && x >= line)
+                .then(|| now - dt * (1.0 - crossing_fraction(last_x, x, line)) - started_at)
+        };
+
+        // Several split lines can be crossed in one frame
+        while let Some(split) = active_course
+            .splits
+            .get(run.splits.len())
+            .and_then(|line| crossed(*line))
+        {
+            info!("Split {} at {:.3}s", run.splits.len() + 1, split);
+            run.splits.push(split);
+        }
+
+        // Progress is timed the same way, up to the finish
+        loop {
+            let line = course.start + (run.progress.len() + 1) as f64 * PROGRESS_INTERVAL;
+            let Some(time) = crossed(line).filter(|_| line < course.finish) else {
+                break;
+            };
+            run.progress.push(time);
+        }
+
+        if let Some(elapsed) = crossed(course.finish) {
+            let result = RunFinished {
+                elapsed,
+                splits: run.splits.clone(),
+                progress: run.progress.clone(),
+                stats: run.stats,
+            };
+
+            info!("Run finished in {:.3}s", result.elapsed);
+            finished_events.send(result.clone());
+            run.result = Some(result);
+        }
+    }
+
+    pub fn load_time_trial_records(mut time_trial: ResMut<TimeTrial>) {
+        match TimeTrialRecords::load(&time_trial.path) {
+            Ok(records) => {
+                time_trial.records = records;
+                time_trial.loaded = true;
+            }
+            Err(err) => {
+                error!(
+                    "Failed to load time trial records {:?}: {}",
+                    time_trial.path, err
+                );
+                time_trial.error = Some(format!("{}", err));
+            }
+        }
+    }
+
+    // Records are kept apart for each course and bike setup, so times are
+    // only ever compared like for like
+    pub fn identify_time_trial(
+        mut time_trial: ResMut<TimeTrial>,
+        active_course: Res<ActiveCourse>,
+        active_track: Res<ActiveTrack>,
+        profile_source: Res<ElevationProfileSource>,
+        terrain_seed: Res<TerrainSeed>,
+        terrain_settings: Res<TerrainSettings>,
+        chainring_radius: Res<ChainringRadius>,
+        cassette_radius: Res<CassetteRadius>,
+    ) {
+        if !(active_course.is_changed()
+            || active_track.is_changed()
+            || profile_source.is_changed()
+            || terrain_seed.is_changed()
+            || terrain_settings.is_changed()
+            || chainring_radius.is_changed()
+            || cassette_radius.is_changed())
+        {
+            return;
+        }
+
+        let course_id = active_course.course.map(|course| {
+            let terrain = match (&active_track.track, &profile_source.profile) {
+                (Some(track), _) => format!("track {:?} #{}", track.name, fingerprint(track)),
+                (None, Some(profile)) => {
+                    format!("profile {:?} #{}", profile.name, fingerprint(profile))
+                }
+                (None, None) => format!(
+                    "seed {} #{}",
+                    terrain_seed.0,
+                    fingerprint(terrain_settings.as_ref())
+                ),
+            };
+            format!("{}, {:.0} m", terrain, course.length() / UNITS_PER_METER)
+        });
+        let bike_id = format!(
+            "chainring {:.1} / cassette {:.1}",
+            chainring_radius.0, cassette_radius.0
+        );
+
+        let time_trial = time_trial.as_mut();
+        time_trial.course_id = course_id;
+        time_trial.bike_id = bike_id;
+        time_trial.comparison = time_trial.personal_best().cloned();
+        time_trial.new_best = false;
+    }
+
+    pub fn record_time_trial_results(
+        mut started_events: EventReader<RunStarted>,
+        mut finished_events: EventReader<RunFinished>,
+        mut time_trial: ResMut<TimeTrial>,
+    ) {
+        let time_trial = time_trial.as_mut();
+
+        // Including any best set by the run before
+        if started_events.read().count() > 0 {
+            time_trial.comparison = time_trial.personal_best().cloned();
+            time_trial.new_best = false;
+        }
+
+        for finished in finished_events.read() {
+            let Some(course_id) = time_trial.course_id.as_ref().filter(|_| time_trial.enabled)
+            else {
+                continue;
+            };
+
+            time_trial.new_best =
+                time_trial
+                    .records
+                    .add(course_id, &time_trial.bike_id, finished.into());
+            if time_trial.new_best {
+                info!(
+                    "New personal best on {}: {:.3}s",
+                    course_id, finished.elapsed
+                );
+            }
+
+            if !time_trial.loaded {
+                continue;
+            }
+            match time_trial.records.save(&time_trial.path) {
+                Ok(()) => time_trial.error = None,
+                Err(err) => {
+                    error!(
+                        "Failed to save time trial records {:?}: {}",
+                        time_trial.path, err
+                    );
+                    time_trial.error = Some(format!("{}", err));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crossing_fraction, fingerprint};
+
+    #[test]
+    fn crossing_fraction_is_where_the_line_falls_in_the_frame() {
+        assert_eq!(crossing_fraction(0.0, 10.0, 2.5), 0.25);
+        assert_eq!(crossing_fraction(0.0, 10.0, 10.0), 1.0);
+        // Lines outside the frame's movement are clamped to its ends
+        assert_eq!(crossing_fraction(0.0, 10.0, -5.0), 0.0);
+        assert_eq!(crossing_fraction(0.0, 10.0, 15.0), 1.0);
+    }
+
+    #[test]
+    fn fingerprint_is_stable() {
+        // Records saved by earlier builds have to keep matching
+        assert_eq!(fingerprint(&"track"), "9f920ed8");
+        assert_eq!(fingerprint(&"track"), fingerprint(&"track"));
+        assert_ne!(fingerprint(&"track"), fingerprint(&"track 2"));
+    }
+}
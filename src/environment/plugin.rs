@@ -0,0 +1,26 @@
+use bevy::prelude::*;
+
+use crate::GameState;
+
+use super::resources::{LitMaterials, Weather, WorldClock};
+
+pub struct EnvironmentPlugin;
+
+impl Plugin for EnvironmentPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorldClock>()
+            .init_resource::<Weather>()
+            .init_resource::<LitMaterials>()
+            .add_systems(
+                Update,
+                (
+                    EnvironmentPlugin::advance_world_clock,
+                    EnvironmentPlugin::light_scene,
+                    EnvironmentPlugin::apply_weather_to_surfaces,
+                    EnvironmentPlugin::draw_precipitation,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Ready)),
+            );
+    }
+}
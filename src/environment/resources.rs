@@ -0,0 +1,105 @@
+use std::f32::consts::TAU;
+
+use avian2d::prelude::{CoefficientCombine, Friction};
+use bevy::{prelude::*, utils::HashMap};
+use strum_macros::EnumIter;
+
+use crate::world::surfaces::SurfaceType;
+
+#[derive(Resource)]
+pub struct WorldClock {
+    // Hours since midnight
+    pub hour: f32,
+    // Real seconds a full day takes
+    pub day_length: f32,
+    pub paused: bool,
+}
+
+impl Default for WorldClock {
+    fn default() -> Self {
+        WorldClock {
+            hour: 10.0,
+            day_length: 600.0,
+            paused: false,
+        }
+    }
+}
+
+impl WorldClock {
+    // 1.0 through the day and 0.0 through the night, blending over dawn and dusk
+    pub fn daylight(&self) -> f32 {
+        // Height of the sun, 1.0 at noon and -1.0 at midnight
+        let sun = ((self.hour - 6.0) / 24.0 * TAU).sin();
+        ((sun + 0.15) / 0.3).clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Debug, EnumIter, PartialEq, Eq, Clone, Copy)]
+pub enum WeatherKind {
+    Clear,
+    Rain,
+    Snow,
+}
+
+#[derive(Resource, Clone, PartialEq)]
+pub struct Weather {
+    pub kind: WeatherKind,
+    // 0.0 is barely any, 1.0 a downpour or blizzard
+    pub intensity: f64,
+    // Horizontal wind speed, positive blowing towards +x
+    pub wind: f64,
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Weather {
+            kind: WeatherKind::Clear,
+            intensity: 0.5,
+            wind: 0.0,
+        }
+    }
+}
+
+impl Weather {
+    // Fraction of a surface's dry grip left in this weather
+    pub fn grip_factor(&self, surface: SurfaceType) -> f64 {
+        let worst = match (self.kind, surface) {
+            (WeatherKind::Clear, _) => 1.0,
+            (WeatherKind::Rain, SurfaceType::Asphalt) => 0.7,
+            (WeatherKind::Rain, SurfaceType::Gravel) => 0.85,
+            (WeatherKind::Rain, SurfaceType::Dirt) => 0.6,
+            (WeatherKind::Rain, SurfaceType::Mud) => 0.7,
+            (WeatherKind::Rain, SurfaceType::Ice) => 0.5,
+            // Wet sand packs down and holds better
+            (WeatherKind::Rain, SurfaceType::Sand) => 1.1,
+            (WeatherKind::Snow, SurfaceType::Ice) => 0.9,
+            (WeatherKind::Snow, _) => 0.45,
+        };
+
+        1.0 + (worst - 1.0) * self.intensity.clamp(0.0, 1.0)
+    }
+
+    pub fn surface_friction(&self, surface: SurfaceType) -> Friction {
+        let grip_factor = self.grip_factor(surface);
+
+        Friction::new(surface.friction() * grip_factor)
+            .with_static_coefficient(surface.grip() * grip_factor)
+            .with_combine_rule(CoefficientCombine::Multiply)
+    }
+
+    // How much the sky is greyed out by cloud cover
+    pub fn overcast(&self) -> f32 {
+        match self.kind {
+            WeatherKind::Clear => 0.0,
+            WeatherKind::Rain | WeatherKind::Snow => self.intensity.clamp(0.0, 1.0) as f32,
+        }
+    }
+}
+
+// The colors the scene's materials were created with, which the light is
+// multiplied over, and the light last applied to them
+#[derive(Resource, Default)]
+pub struct LitMaterials {
+    pub light: Option<LinearRgba>,
+    pub colors: HashMap<AssetId<StandardMaterial>, Color>,
+}
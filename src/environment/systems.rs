@@ -0,0 +1,229 @@
+use avian2d::prelude::Friction;
+use bevy::{
+    color::{
+        palettes::{
+            css::{MIDNIGHT_BLUE, SLATE_GRAY, WHITE},
+            tailwind::BLUE_400,
+        },
+        Mix,
+    },
+    prelude::*,
+    utils::HashSet,
+};
+
+use crate::{
+    camera::{
+        components::FollowCamera,
+        parallax::{LayerTime, ParallaxLayerQuad, ParallaxLayers},
+    },
+    world::{
+        components::TerrainSurface,
+        material::{TerrainMaterial, TerrainMaterialHandle},
+    },
+};
+
+use super::{
+    plugin::EnvironmentPlugin,
+    resources::{LitMaterials, Weather, WeatherKind, WorldClock},
+};
+
+// Drops or flakes on screen at full intensity
+const MAX_PRECIPITATION: f64 = 400.0;
+// In front of the terrain and the bicycle
+const PRECIPITATION_Z: f32 = 50.0;
+
+// Same light on the terrain, the parallax and every other material, so the
+// scene darkens evenly at night
+fn scene_light(clock: &WorldClock, weather: &Weather) -> LinearRgba {
+    let night: LinearRgba = Srgba::rgb(0.25, 0.3, 0.45).into();
+    let light = night.mix(&WHITE.into(), clock.daylight());
+    let brightness = 1.0 - 0.35 * weather.overcast();
+
+    LinearRgba::rgb(
+        light.red * brightness,
+        light.green * brightness,
+        light.blue * brightness,
+    )
+}
+
+fn lit(color: Color, light: LinearRgba) -> Color {
+    let color = color.to_linear();
+    LinearRgba::new(
+        color.red * light.red,
+        color.green * light.green,
+        color.blue * light.blue,
+        color.alpha,
+    )
+    .into()
+}
+
+// Cheap repeatable scatter in [0, 1)
+fn scatter(seed: f32) -> f32 {
+    (seed.sin() * 43758.545).fract().abs()
+}
+
+impl EnvironmentPlugin {
+    pub fn advance_world_clock(mut clock: ResMut<WorldClock>, time: Res<Time>) {
+        if clock.paused {
+            return;
+        }
+
+        let hours = 24.0 * time.delta_secs() / clock.day_length.max(1.0);
+        clock.hour = (clock.hour + hours).rem_euclid(24.0);
+    }
+
+    pub fn light_scene(
+        clock: Res<WorldClock>,
+        weather: Res<Weather>,
+        mut clear_color: ResMut<ClearColor>,
+        terrain_material: Res<TerrainMaterialHandle>,
+        mut terrain_materials: ResMut<Assets<TerrainMaterial>>,
+        layers: Res<ParallaxLayers>,
+        quads: Query<(&ParallaxLayerQuad, &MeshMaterial3d<StandardMaterial>)>,
+        mut materials: ResMut<Assets<StandardMaterial>>,
+        mut lit_materials: ResMut<LitMaterials>,
+    ) {
+        let daylight = clock.daylight();
+        let light = scene_light(&clock, &weather);
+
+        let day_sky: LinearRgba = BLUE_400.into();
+        let sky = LinearRgba::from(MIDNIGHT_BLUE)
+            .mix(&day_sky, daylight)
+            .mix(&SLATE_GRAY.into(), 0.6 * weather.overcast());
+        clear_color.0 = sky.into();
+
+        // Only touch materials when they change, as each write re-uploads them
+        if terrain_materials
+            .get(&terrain_material.0)
+            .is_some_and(|material| material.light != light)
+        {
+            if let Some(material) = terrain_materials.get_mut(&terrain_material.0) {
+                material.light = light;
+            }
+        }
+
+        // The day and night skies fade across each other over dawn and dusk
+        for (quad, material) in quads.iter() {
+            let Some(layer) = layers.0.get(quad.0) else {
+                continue;
+            };
+
+            let alpha = match layer.time {
+                LayerTime::Always => 1.0,
+                LayerTime::Day => daylight,
+                LayerTime::Night => 1.0 - daylight,
+            };
+            let color: Color = light.with_alpha(alpha).into();
+
+            if materials
+                .get(material)
+                .is_some_and(|material| material.base_color != color)
+            {
+                if let Some(material) = materials.get_mut(material) {
+                    material.base_color = color;
+                }
+            }
+        }
+
+        // Everything else (the bicycle, terrain features and track props)
+        // keeps its own color under the light. Materials created since
+        // the last frame, such as those of new chunks, start out unlit.
+        let parallax: HashSet<_> = quads.iter().map(|(_, material)| material.id()).collect();
+        let fresh: Vec<_> = materials
+            .ids()
+            .filter(|id| !parallax.contains(id) && !lit_materials.colors.contains_key(id))
+            .collect();
+        for id in fresh {
+            if let Some(material) = materials.get_mut(id) {
+                lit_materials.colors.insert(id, material.base_color);
+                material.base_color = lit(material.base_color, light);
+            }
+        }
+
+        // Parallax quads are spawned a frame after their materials are added
+        lit_materials.colors.retain(|id, _| !parallax.contains(id));
+
+        if lit_materials.light != Some(light) {
+            lit_materials.light = Some(light);
+            lit_materials
+                .colors
+                .retain(|&id, &mut color| match materials.get_mut(id) {
+                    Some(material) => {
+                        material.base_color = lit(color, light);
+                        true
+                    }
+                    None => false,
+                });
+        }
+    }
+
+    // Wetness and snow cover take grip from the terrain surfaces, including
+    // those of chunks spawned since the weather last changed
+    pub fn apply_weather_to_surfaces(
+        weather: Res<Weather>,
+        mut surfaces: Query<(Ref<TerrainSurface>, &mut Friction)>,
+    ) {
+        for (surface, mut friction) in surfaces.iter_mut() {
+            if weather.is_changed() || surface.is_added() {
+                *friction = weather.surface_friction(surface.0);
+            }
+        }
+    }
+
+    pub fn draw_precipitation(
+        mut gizmos: Gizmos,
+        weather: Res<Weather>,
+        camera: Query<(&Camera, &Transform, &Projection), With<FollowCamera>>,
+        time: Res<Time>,
+    ) {
+        let (fall_speed, color) = match weather.kind {
+            WeatherKind::Clear => return,
+            WeatherKind::Rain => (600.0, Color::srgba(0.7, 0.8, 1.0, 0.6)),
+            WeatherKind::Snow => (80.0, Color::WHITE),
+        };
+
+        let Ok((camera, camera_t, projection)) = camera.get_single() else {
+            return;
+        };
+        let Some(viewport) = camera.logical_viewport_size() else {
+            return;
+        };
+        let scale = match projection {
+            Projection::Orthographic(ortho) => ortho.scale,
+            _ => 1.0,
+        };
+        let view = viewport * scale;
+        let corner = camera_t.translation.truncate() - view / 2.0;
+
+        let t = time.elapsed_secs();
+        let velocity = Vec2::new(weather.wind as f32, -fall_speed);
+        let count = (MAX_PRECIPITATION * weather.intensity.clamp(0.0, 1.0)) as usize;
+
+        for i in 0..count {
+            // Each drop keeps its place in the world, wrapped around the view
+            // so there are always enough on screen
+            let start = Vec2::new(scatter(i as f32 * 12.9898), scatter(i as f32 * 78.233)) * view;
+            let mut position = (start + velocity * t - corner).rem_euclid(view) + corner;
+
+            match weather.kind {
+                WeatherKind::Rain => {
+                    let streak = velocity.normalize() * 12.0 * scale;
+                    gizmos.line(
+                        position.extend(PRECIPITATION_Z),
+                        (position + streak).extend(PRECIPITATION_Z),
+                        color,
+                    );
+                }
+                WeatherKind::Snow => {
+                    position.x += (t + i as f32).sin() * 6.0 * scale;
+                    gizmos.circle(
+                        Isometry3d::from_translation(position.extend(PRECIPITATION_Z)),
+                        1.5 * scale,
+                        color,
+                    );
+                }
+                WeatherKind::Clear => {}
+            }
+        }
+    }
+}
@@ -4,7 +4,12 @@
 #![allow(rustdoc::private_intra_doc_links)]
 
 pub mod bicycle;
+pub mod bounded_queue;
 pub mod camera;
+pub mod course;
+pub mod environment;
+pub mod telemetry;
+pub mod track;
 pub mod ui;
 pub mod user_input;
 pub mod world;
@@ -18,8 +23,6 @@ use bevy::{
     color::palettes::{css::WHITE, tailwind::BLUE_400},
     pbr::wireframe::WireframeConfig,
     prelude::*,
-    render::render_resource::{AsBindGroup, ShaderRef},
-    sprite::Material2d,
     utils::HashMap,
 };
 use bevy_egui::EguiPlugin;
@@ -27,6 +30,10 @@ use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_sprite3d::Sprite3dPlugin;
 use bicycle::plugin::BicyclePlugin;
 use camera::plugin::CameraPlugin;
+use course::plugin::CoursePlugin;
+use environment::plugin::EnvironmentPlugin;
+use telemetry::plugin::TelemetryPlugin;
+use track::plugin::TrackPlugin;
 use ui::plugin::UIPlugin;
 use user_input::plugin::UserInputPlugin;
 use world::plugin::WorldTerrainPlugin;
@@ -41,6 +48,7 @@ pub enum GameLayer {
     AttachmentPoints,
     Groupset,
     Chain,
+    Props,
 }
 
 pub struct GamePlugin;
@@ -64,12 +72,15 @@ impl Plugin for GamePlugin {
             EguiPlugin,
             PhysicsPlugins::default(),
             PhysicsDebugPlugin::default(),
-            // Material2dPlugin::<CustomMaterial>::default(),
             UIPlugin,
             WorldTerrainPlugin,
             CameraPlugin,
             BicyclePlugin,
             UserInputPlugin,
+            TelemetryPlugin,
+            TrackPlugin,
+            CoursePlugin,
+            EnvironmentPlugin,
             Sprite3dPlugin,
             // WireframePlugin,
         ))
@@ -131,75 +142,3 @@ fn setup(
     info!("ASSETS LOADED -> READY");
     next_state.set(GameState::Ready);
 }
-
-// This struct defines the data that will be passed to your shader
-#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
-pub struct CustomMaterial {
-    #[uniform(0)]
-    color: LinearRgba,
-    #[texture(1)]
-    #[sampler(2)]
-    color_texture: Option<Handle<Image>>,
-    // alpha_mode: AlphaMode,
-}
-
-/// The Material trait is very configurable, but comes with sensible defaults for all methods.
-/// You only need to implement functions for features that need non-default behavior. See the Material api docs for details!
-impl Material2d for CustomMaterial {
-    fn fragment_shader() -> ShaderRef {
-        "shaders/custom_material_2d.wgsl".into()
-    }
-
-    // fn alpha_mode(&self) -> AlphaMode {
-    //     self.alpha_mode
-    // }
-}
-
-use std::collections::VecDeque;
-
-#[derive(Clone)]
-struct BoundedQueue<T> {
-    queue: VecDeque<T>,
-    max_size: usize,
-}
-
-impl<T> BoundedQueue<T> {
-    fn new(max_size: usize) -> Self {
-        BoundedQueue {
-            queue: VecDeque::with_capacity(max_size),
-            max_size,
-        }
-    }
-
-    fn enqueue(&mut self, item: T) {
-        if self.queue.len() == self.max_size {
-            self.queue.pop_front(); // Remove the oldest item
-        }
-        self.queue.push_back(item); // Add the new item
-    }
-
-    fn dequeue(&mut self) -> Option<T> {
-        self.queue.pop_front() // Remove the front item
-    }
-
-    fn peek(&self) -> Option<&T> {
-        self.queue.front() // Peek at the front item
-    }
-
-    fn len(&self) -> usize {
-        self.queue.len() // Get the current length of the queue
-    }
-
-    fn is_empty(&self) -> bool {
-        self.queue.is_empty() // Check if the queue is empty
-    }
-}
-
-// Implementing the Iterator trait for BoundedQueue
-impl<T> Iterator for BoundedQueue<T> {
-    type Item = T;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.queue.pop_front() // Remove and return the front element
-    }
-}
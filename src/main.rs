@@ -1,6 +1,17 @@
 use bevy::prelude::*;
-use bevy_bicycle::GamePlugin;
+use bevy_bicycle::{world::resources::TerrainSeed, GamePlugin};
 
 fn main() {
-    App::new().add_plugins(GamePlugin).run();
+    let mut app = App::new();
+
+    // Inserted before the plugins so the world keeps it instead of a random seed
+    match TerrainSeed::from_args(std::env::args().skip(1)) {
+        Ok(Some(seed)) => {
+            app.insert_resource(seed);
+        }
+        Ok(None) => {}
+        Err(err) => eprintln!("Ignoring --seed: {}", err),
+    }
+
+    app.add_plugins(GamePlugin).run();
 }
@@ -0,0 +1,4 @@
+pub mod events;
+pub mod plugin;
+pub mod resources;
+pub mod systems;
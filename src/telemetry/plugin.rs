@@ -0,0 +1,28 @@
+use bevy::prelude::*;
+
+use crate::{ui::plugin::UIPlugin, GameState};
+
+use super::{
+    events::ToggleTelemetryRecordingEvent,
+    resources::{TelemetryConfig, TelemetryRecorder},
+};
+
+pub struct TelemetryPlugin;
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TelemetryConfig>()
+            .init_resource::<TelemetryRecorder>()
+            .add_event::<ToggleTelemetryRecordingEvent>()
+            .add_systems(
+                Update,
+                (
+                    TelemetryPlugin::handle_toggle_recording_event,
+                    TelemetryPlugin::record_telemetry_sample
+                        .after(UIPlugin::measure_bicycle_statistics),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Ready)),
+            );
+    }
+}
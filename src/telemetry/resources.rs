@@ -0,0 +1,85 @@
+use std::{collections::BTreeMap, fs::File, io::BufWriter, path::PathBuf};
+
+use bevy::prelude::Resource;
+use serde::Serialize;
+
+use crate::ui::systems::BicycleStat;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelemetryFormat {
+    Csv,
+    JsonLines,
+}
+
+impl TelemetryFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TelemetryFormat::Csv => "csv",
+            TelemetryFormat::JsonLines => "jsonl",
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct TelemetryConfig {
+    pub format: TelemetryFormat,
+    pub directory: PathBuf,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        TelemetryConfig {
+            format: TelemetryFormat::Csv,
+            directory: PathBuf::from("telemetry"),
+        }
+    }
+}
+
+// One recorded line, as a CSV row or a JSON object
+#[derive(Debug, Clone, Serialize)]
+pub struct TelemetrySample {
+    pub time: f64,
+    pub x: f64,
+    pub y: f64,
+    pub chunk_index: i128,
+    pub chainring_radius: f32,
+    pub cassette_radius: f32,
+    pub gear_ratio: f64,
+    // NaN until a stat has been measured, which JSON writes as null
+    pub stats: BTreeMap<BicycleStat, f64>,
+}
+
+impl TelemetrySample {
+    pub fn csv_row(&self) -> String {
+        let stat_values: Vec<String> = self.stats.values().map(f64::to_string).collect();
+        format!(
+            "{},{},{},{},{},{},{},{}",
+            self.time,
+            self.x,
+            self.y,
+            self.chunk_index,
+            self.chainring_radius,
+            self.cassette_radius,
+            self.gear_ratio,
+            stat_values.join(",")
+        )
+    }
+}
+
+pub struct TelemetryFile {
+    pub path: PathBuf,
+    pub format: TelemetryFormat,
+    pub writer: BufWriter<File>,
+    pub samples: usize,
+}
+
+#[derive(Resource, Default)]
+pub struct TelemetryRecorder {
+    pub file: Option<TelemetryFile>,
+}
+
+impl TelemetryRecorder {
+    pub fn is_recording(&self) -> bool {
+        self.file.is_some()
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use avian2d::prelude::{Physics, Position};
+use bevy::prelude::*;
+use strum::IntoEnumIterator;
+
+use crate::{
+    bicycle::{
+        frame::BicycleFrame,
+        groupset::{CassetteRadius, ChainringRadius},
+    },
+    ui::systems::{BicycleStat, BicycleStats},
+    world::plugin::WorldTerrainPlugin,
+};
+
+use super::{
+    events::ToggleTelemetryRecordingEvent,
+    plugin::TelemetryPlugin,
+    resources::{
+        TelemetryConfig, TelemetryFile, TelemetryFormat, TelemetryRecorder, TelemetrySample,
+    },
+};
+
+const CSV_HEADER: &str = "time,x,y,chunk_index,chainring_radius,cassette_radius,gear_ratio";
+
+impl TelemetryPlugin {
+    pub fn handle_toggle_recording_event(
+        mut events: EventReader<ToggleTelemetryRecordingEvent>,
+        config: Res<TelemetryConfig>,
+        mut recorder: ResMut<TelemetryRecorder>,
+    ) {
+        for _evt in events.read() {
+            match recorder.file.take() {
+                Some(mut file) => {
+                    if let Err(err) = file.writer.flush() {
+                        error!("Failed to flush telemetry to {:?}: {}", file.path, err);
+                    }
+                    info!(
+                        "Stopped recording telemetry: {} samples written to {:?}",
+                        file.samples, file.path
+                    );
+                }
+                None => match Self::create_telemetry_file(&config) {
+                    Ok(file) => {
+                        info!("Recording telemetry to {:?}", file.path);
+                        recorder.file = Some(file);
+                    }
+                    Err(err) => error!("Failed to start recording telemetry: {}", err),
+                },
+            }
+        }
+    }
+
+    fn create_telemetry_file(config: &TelemetryConfig) -> io::Result<TelemetryFile> {
+        fs::create_dir_all(&config.directory)?;
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let path = config.directory.join(format!(
+            "telemetry-{}.{}",
+            timestamp,
+            config.format.extension()
+        ));
+
+        let mut writer = BufWriter::new(File::create(&path)?);
+
+        if config.format == TelemetryFormat::Csv {
+            let stat_columns: Vec<String> = BicycleStat::iter()
+                .map(|stat| format!("{:?}", stat))
+                .collect();
+            writeln!(writer, "{},{}", CSV_HEADER, stat_columns.join(","))?;
+        }
+
+        Ok(TelemetryFile {
+            path,
+            format: config.format,
+            writer,
+            samples: 0,
+        })
+    }
+
+    pub fn record_telemetry_sample(
+        frame: Query<&Position, With<BicycleFrame>>,
+        physics_time: Res<Time<Physics>>,
+        chainring_radius: Res<ChainringRadius>,
+        cassette_radius: Res<CassetteRadius>,
+        bicycle_stats: Res<BicycleStats>,
+        mut recorder: ResMut<TelemetryRecorder>,
+    ) {
+        let Some(file) = recorder.file.as_mut() else {
+            return;
+        };
+        let Ok(position) = frame.get_single() else {
+            return;
+        };
+
+        let sample = TelemetrySample {
+            time: physics_time.elapsed_secs_f64(),
+            x: position.x,
+            y: position.y,
+            chunk_index: WorldTerrainPlugin::x_pos_to_chunk_index(position.x),
+            chainring_radius: chainring_radius.0,
+            cassette_radius: cassette_radius.0,
+            gear_ratio: chainring_radius.0 as f64 / cassette_radius.0 as f64,
+            stats: BicycleStat::iter()
+                .map(|stat| (stat, bicycle_stats.latest(&stat).unwrap_or(f64::NAN)))
+                .collect(),
+        };
+
+        let result = match file.format {
+            TelemetryFormat::Csv => writeln!(file.writer, "{}", sample.csv_row()),
+            TelemetryFormat::JsonLines => serde_json::to_writer(&mut file.writer, &sample)
+                .map_err(io::Error::from)
+                .and_then(|()| writeln!(file.writer)),
+        };
+
+        match result {
+            Ok(()) => file.samples += 1,
+            Err(err) => {
+                error!("Failed to write telemetry to {:?}: {}", file.path, err);
+                recorder.file = None;
+            }
+        }
+    }
+}
@@ -0,0 +1,8 @@
+use bevy::prelude::*;
+
+// Parent of the checkpoints spawned for the active track
+#[derive(Component)]
+pub struct TrackProps;
+
+#[derive(Component)]
+pub struct Checkpoint(pub usize);
@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use bevy::{
+    color::palettes::css::{RED, WHITE, YELLOW},
+    math::DVec2,
+    prelude::*,
+    window::PrimaryWindow,
+};
+use bevy_egui::EguiContexts;
+
+use crate::{camera::components::FollowCamera, world::generators::TerrainGenerator};
+
+use super::{
+    format::{ControlPoint, TrackFeature},
+    plugin::TrackPlugin,
+    resources::{ActiveTrack, TrackEditor},
+};
+
+// Control points within this many pixels of the cursor can be picked up
+const PICK_RADIUS_PIXELS: f32 = 12.0;
+// In front of the terrain and track props
+const EDITOR_Z: f32 = 20.0;
+
+fn cursor_world_position(
+    window: &Window,
+    camera: &Camera,
+    camera_gt: &GlobalTransform,
+) -> Option<DVec2> {
+    let cursor = window.cursor_position()?;
+    let ray = camera.viewport_to_world(camera_gt, cursor).ok()?;
+    Some(ray.origin.truncate().as_dvec2())
+}
+
+// World units per screen pixel
+fn camera_scale(projection: &Projection) -> f32 {
+    match projection {
+        Projection::Orthographic(ortho) => ortho.scale,
+        _ => 1.0,
+    }
+}
+
+impl TrackPlugin {
+    // Left click picks up and drags a control point, or places whatever the
+    // current tool makes. Right click removes a control point. A dragged point
+    // is kept in the editor's draft and only applied to the track, which
+    // rebuilds the terrain, once it's let go.
+    pub fn edit_track_with_pointer(
+        mut editor: ResMut<TrackEditor>,
+        mut active_track: ResMut<ActiveTrack>,
+        buttons: Res<ButtonInput<MouseButton>>,
+        windows: Query<&Window, With<PrimaryWindow>>,
+        camera: Query<(&Camera, &GlobalTransform, &Projection), With<FollowCamera>>,
+        mut contexts: EguiContexts,
+    ) {
+        if !editor.enabled {
+            return;
+        }
+
+        if buttons.just_released(MouseButton::Left) && editor.dragging {
+            editor.dragging = false;
+
+            if let Some(draft) = editor.draft.take() {
+                if active_track.track.as_deref() != Some(&draft) {
+                    active_track.track = Some(Arc::new(draft));
+                }
+            }
+        }
+
+        // Clicks on the egui windows belong to the UI
+        if !editor.dragging && contexts.ctx_mut().is_pointer_over_area() {
+            return;
+        }
+
+        let (Ok(window), Ok((camera, camera_gt, projection))) =
+            (windows.get_single(), camera.get_single())
+        else {
+            return;
+        };
+        let Some(cursor) = cursor_world_position(window, camera, camera_gt) else {
+            return;
+        };
+        let Some(track) = active_track.track.as_deref() else {
+            return;
+        };
+        let track = editor.draft.as_ref().unwrap_or(track);
+
+        let pick_radius = (PICK_RADIUS_PIXELS * camera_scale(projection)) as f64;
+        let hovered = track
+            .control_points
+            .iter()
+            .position(|point| DVec2::new(point.x, point.height).distance(cursor) <= pick_radius);
+
+        if buttons.just_pressed(MouseButton::Left) {
+            if let Some(index) = hovered {
+                editor.draft = Some(track.clone());
+                editor.selected = Some(index);
+                editor.dragging = true;
+                return;
+            }
+
+            match editor.tool.feature_at(cursor.x) {
+                Some(feature) => {
+                    let track = Arc::make_mut(active_track.track.as_mut().unwrap());
+                    track.features.push(feature);
+                }
+                // A new point is dragged into place before it's added to the track
+                None => {
+                    let mut draft = track.clone();
+                    let index = draft
+                        .control_points
+                        .partition_point(|point| point.x <= cursor.x);
+                    draft.control_points.insert(
+                        index,
+                        ControlPoint {
+                            x: cursor.x,
+                            height: cursor.y,
+                            surface: track.surface(cursor.x),
+                        },
+                    );
+
+                    editor.draft = Some(draft);
+                    editor.selected = Some(index);
+                    editor.dragging = true;
+                }
+            }
+        } else if buttons.pressed(MouseButton::Left) && editor.dragging {
+            let editor = editor.as_mut();
+            let (Some(index), Some(draft)) = (editor.selected, editor.draft.as_mut()) else {
+                return;
+            };
+            if index >= draft.control_points.len() {
+                return;
+            }
+
+            let x_range = draft.control_point_x_range(index);
+            let point = &mut draft.control_points[index];
+            point.x = cursor.x.clamp(*x_range.start(), *x_range.end());
+            point.height = cursor.y;
+        } else if buttons.just_pressed(MouseButton::Right) {
+            let Some(index) = hovered else {
+                return;
+            };
+
+            if track.control_points.len() > 2 {
+                let track = Arc::make_mut(active_track.track.as_mut().unwrap());
+                track.control_points.remove(index);
+                editor.selected = None;
+            }
+        }
+    }
+
+    pub fn draw_track_editor(
+        mut gizmos: Gizmos,
+        editor: Res<TrackEditor>,
+        active_track: Res<ActiveTrack>,
+        camera: Query<&Projection, With<FollowCamera>>,
+    ) {
+        if !editor.enabled {
+            return;
+        }
+        let Some(track) = editor.draft.as_ref().or(active_track.track.as_deref()) else {
+            return;
+        };
+
+        let scale = camera.get_single().map(camera_scale).unwrap_or(1.0);
+        let radius = PICK_RADIUS_PIXELS * scale / 2.0;
+        let to_editor_plane = |x: f64, y: f64| Vec3::new(x as f32, y as f32, EDITOR_Z);
+
+        gizmos.linestrip(
+            track
+                .control_points
+                .iter()
+                .map(|point| to_editor_plane(point.x, point.height)),
+            WHITE,
+        );
+
+        for (index, point) in track.control_points.iter().enumerate() {
+            let color = if editor.selected == Some(index) {
+                YELLOW
+            } else {
+                WHITE
+            };
+            gizmos.circle(
+                Isometry3d::from_translation(to_editor_plane(point.x, point.height)),
+                radius,
+                color,
+            );
+        }
+
+        // Outline gaps, which otherwise just look like missing terrain
+        for feature in track.features.iter() {
+            if let TrackFeature::Gap { x, width } = *feature {
+                let left = to_editor_plane(x, track.ground_height(x));
+                let right = to_editor_plane(x + width, track.ground_height(x + width));
+                gizmos.line(left, right, RED);
+            }
+        }
+    }
+}
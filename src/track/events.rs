@@ -0,0 +1,14 @@
+use std::path::PathBuf;
+
+use bevy::prelude::Event;
+
+#[derive(Event)]
+pub enum TrackEvent {
+    New,
+    Load(PathBuf),
+    Save(PathBuf),
+    Close,
+}
+
+#[derive(Event)]
+pub struct CheckpointReachedEvent(pub usize);
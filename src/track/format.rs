@@ -0,0 +1,561 @@
+use std::{error::Error, fmt, fs, io, ops::RangeInclusive, path::Path};
+
+use bevy::math::DVec2;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    course::resources::Course,
+    world::{
+        contraptions::{ContraptionKind, PlacedContraption},
+        features::{FeatureKind, PlacedFeature},
+        generators::TerrainGenerator,
+        surfaces::SurfaceType,
+    },
+};
+
+pub const TRACK_EXTENSION: &str = "ron";
+// Keeps neighbouring control points from crossing while they're edited
+const MIN_CONTROL_POINT_SPACING: f64 = 1.0;
+// Smallest length, height, span or width a feature can be given, so its
+// colliders never collapse
+pub const MIN_FEATURE_SIZE: f64 = 1.0;
+// Range of seconds a platform can take to move out and back
+pub const PLATFORM_PERIODS: RangeInclusive<f64> = 0.5..=60.0;
+// Bridges get a plank about this long for every stretch of their span
+const BRIDGE_PLANK_LENGTH: f64 = 25.0;
+
+#[derive(Debug)]
+pub enum TrackError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+    TooFewControlPoints,
+    // Parsed, but describes a track that can't be built
+    Invalid(String),
+}
+
+impl fmt::Display for TrackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrackError::Io(err) => write!(f, "{}", err),
+            TrackError::Parse(err) => write!(f, "{}", err),
+            TrackError::Serialize(err) => write!(f, "{}", err),
+            TrackError::TooFewControlPoints => {
+                write!(f, "a track needs at least two control points")
+            }
+            TrackError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for TrackError {}
+
+impl From<io::Error> for TrackError {
+    fn from(err: io::Error) -> Self {
+        TrackError::Io(err)
+    }
+}
+
+// Terrain height at x. The segment running to the next control point is made
+// of `surface`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ControlPoint {
+    pub x: f64,
+    pub height: f64,
+    #[serde(default = "ControlPoint::default_surface")]
+    pub surface: SurfaceType,
+}
+
+impl ControlPoint {
+    fn default_surface() -> SurfaceType {
+        SurfaceType::Dirt
+    }
+}
+
+// Features placed along the track, positioned by their left edge
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TrackFeature {
+    // Wedge rising from the ground to `height` at its far end
+    Ramp {
+        x: f64,
+        length: f64,
+        height: f64,
+    },
+    // Stretch with no ground at all
+    Gap {
+        x: f64,
+        width: f64,
+    },
+    // Solid block sitting on the ground
+    Obstacle {
+        x: f64,
+        width: f64,
+        height: f64,
+    },
+    Checkpoint {
+        x: f64,
+    },
+    // Plank that tips over a pivot halfway along it
+    Seesaw {
+        x: f64,
+        length: f64,
+    },
+    // Plank bridge hung across a gap of `span`
+    Bridge {
+        x: f64,
+        span: f64,
+    },
+    // Deck moving out by (travel_x, travel_y) and back every `period` seconds
+    Platform {
+        x: f64,
+        width: f64,
+        travel_x: f64,
+        travel_y: f64,
+        period: f64,
+    },
+}
+
+impl TrackFeature {
+    pub fn x(&self) -> f64 {
+        match self {
+            TrackFeature::Ramp { x, .. }
+            | TrackFeature::Gap { x, .. }
+            | TrackFeature::Obstacle { x, .. }
+            | TrackFeature::Checkpoint { x }
+            | TrackFeature::Seesaw { x, .. }
+            | TrackFeature::Bridge { x, .. }
+            | TrackFeature::Platform { x, .. } => *x,
+        }
+    }
+
+    pub fn x_mut(&mut self) -> &mut f64 {
+        match self {
+            TrackFeature::Ramp { x, .. }
+            | TrackFeature::Gap { x, .. }
+            | TrackFeature::Obstacle { x, .. }
+            | TrackFeature::Checkpoint { x }
+            | TrackFeature::Seesaw { x, .. }
+            | TrackFeature::Bridge { x, .. }
+            | TrackFeature::Platform { x, .. } => x,
+        }
+    }
+}
+
+// Hand-authored course: terrain is linear between control points, and flat
+// beyond the first and last.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Track {
+    pub name: String,
+    pub control_points: Vec<ControlPoint>,
+    #[serde(default)]
+    pub features: Vec<TrackFeature>,
+    // Start gate and finish line, if the track is raced
+    #[serde(default)]
+    pub course: Option<Course>,
+}
+
+impl Track {
+    // Flat starter track spanning `width` around x
+    pub fn flat(x: f64, height: f64, width: f64) -> Self {
+        Track {
+            name: "Untitled".to_string(),
+            control_points: vec![
+                ControlPoint {
+                    x: x - width / 2.0,
+                    height,
+                    surface: ControlPoint::default_surface(),
+                },
+                ControlPoint {
+                    x: x + width / 2.0,
+                    height,
+                    surface: ControlPoint::default_surface(),
+                },
+            ],
+            features: vec![],
+            course: None,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, TrackError> {
+        let text = fs::read_to_string(path)?;
+        let mut track: Track = ron::from_str(&text).map_err(TrackError::Parse)?;
+
+        track.sort_control_points();
+        track.validate()?;
+
+        Ok(track)
+    }
+
+    // Checks a hand-edited file for values the terrain and props can't be
+    // built from. Control points are expected to be sorted.
+    pub fn validate(&self) -> Result<(), TrackError> {
+        if self.control_points.len() < 2 {
+            return Err(TrackError::TooFewControlPoints);
+        }
+
+        for (index, point) in self.control_points.iter().enumerate() {
+            if !point.x.is_finite() || !point.height.is_finite() {
+                return Err(TrackError::Invalid(format!(
+                    "control point {} must have a finite x and height",
+                    index + 1
+                )));
+            }
+        }
+
+        for (index, pair) in self.control_points.windows(2).enumerate() {
+            if pair[1].x - pair[0].x < MIN_CONTROL_POINT_SPACING {
+                return Err(TrackError::Invalid(format!(
+                    "control points {} and {} must be at least {} apart",
+                    index + 1,
+                    index + 2,
+                    MIN_CONTROL_POINT_SPACING
+                )));
+            }
+        }
+
+        for (index, feature) in self.features.iter().enumerate() {
+            let invalid = |message: &str| {
+                TrackError::Invalid(format!(
+                    "feature {} ({:?}): {}",
+                    index + 1,
+                    feature,
+                    message
+                ))
+            };
+
+            if !feature.x().is_finite() {
+                return Err(invalid("x must be finite"));
+            }
+
+            let sizes: &[f64] = match feature {
+                TrackFeature::Ramp { length, height, .. } => &[*length, *height],
+                TrackFeature::Gap { width, .. } => &[*width],
+                TrackFeature::Obstacle { width, height, .. } => &[*width, *height],
+                TrackFeature::Checkpoint { .. } => &[],
+                TrackFeature::Seesaw { length, .. } => &[*length],
+                TrackFeature::Bridge { span, .. } => &[*span],
+                TrackFeature::Platform { width, .. } => &[*width],
+            };
+            // Also rejects NaN, which fails every comparison
+            if !sizes
+                .iter()
+                .all(|size| (MIN_FEATURE_SIZE..=f64::MAX).contains(size))
+            {
+                return Err(invalid(&format!(
+                    "sizes must be finite and at least {}",
+                    MIN_FEATURE_SIZE
+                )));
+            }
+
+            if let TrackFeature::Platform {
+                travel_x,
+                travel_y,
+                period,
+                ..
+            } = feature
+            {
+                if !travel_x.is_finite() || !travel_y.is_finite() {
+                    return Err(invalid("travel must be finite"));
+                }
+                if !PLATFORM_PERIODS.contains(period) {
+                    return Err(invalid(&format!(
+                        "period must be between {} and {} seconds",
+                        PLATFORM_PERIODS.start(),
+                        PLATFORM_PERIODS.end()
+                    )));
+                }
+            }
+        }
+
+        if let Some(course) = &self.course {
+            if !course.start.is_finite() || !course.finish.is_finite() {
+                return Err(TrackError::Invalid(
+                    "course start and finish must be finite".to_string(),
+                ));
+            }
+            if course.start >= course.finish {
+                return Err(TrackError::Invalid(format!(
+                    "course start ({}) must be before its finish ({})",
+                    course.start, course.finish
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), TrackError> {
+        let text = ron::ser::to_string_pretty(self, PrettyConfig::default())
+            .map_err(TrackError::Serialize)?;
+
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, text)?;
+
+        Ok(())
+    }
+
+    pub fn sort_control_points(&mut self) {
+        self.control_points.sort_by(|a, b| a.x.total_cmp(&b.x));
+    }
+
+    // Where the control point at index can move without passing a neighbour
+    pub fn control_point_x_range(&self, index: usize) -> RangeInclusive<f64> {
+        let min_x = index
+            .checked_sub(1)
+            .map(|previous| self.control_points[previous].x + MIN_CONTROL_POINT_SPACING)
+            .unwrap_or(f64::MIN);
+        let max_x = self
+            .control_points
+            .get(index + 1)
+            .map(|next| next.x - MIN_CONTROL_POINT_SPACING)
+            .unwrap_or(f64::MAX);
+
+        min_x..=max_x
+    }
+
+    // Checkpoints in the order they're reached along the track
+    pub fn checkpoints(&self) -> Vec<f64> {
+        let mut checkpoints: Vec<f64> = self
+            .features
+            .iter()
+            .filter_map(|feature| match feature {
+                TrackFeature::Checkpoint { x } => Some(*x),
+                _ => None,
+            })
+            .collect();
+        checkpoints.sort_by(f64::total_cmp);
+        checkpoints
+    }
+
+    // Index of the control point starting the segment that contains x
+    fn segment_index(&self, x: f64) -> usize {
+        self.control_points
+            .partition_point(|point| point.x <= x)
+            .saturating_sub(1)
+    }
+
+    pub fn ground_height(&self, x: f64) -> f64 {
+        let index = self.segment_index(x);
+        let start = self.control_points[index];
+
+        match self.control_points.get(index + 1) {
+            Some(end) if x > start.x => {
+                start.height + (end.height - start.height) * (x - start.x) / (end.x - start.x)
+            }
+            _ => start.height,
+        }
+    }
+}
+
+impl TerrainGenerator for Track {
+    fn height(&self, x: f64) -> f64 {
+        self.ground_height(x)
+    }
+
+    fn surface(&self, x: f64) -> SurfaceType {
+        self.control_points[self.segment_index(x)].surface
+    }
+
+    fn is_gap(&self, x: f64) -> bool {
+        self.features.iter().any(|feature| match feature {
+            TrackFeature::Gap { x: start, width }
+            | TrackFeature::Bridge {
+                x: start,
+                span: width,
+            } => (*start..start + width).contains(&x),
+            _ => false,
+        })
+    }
+
+    fn features(&self, start: f64, end: f64) -> Vec<PlacedFeature> {
+        self.features
+            .iter()
+            .filter(|feature| (start..end).contains(&feature.x()))
+            .filter_map(|feature| {
+                let kind = match *feature {
+                    TrackFeature::Ramp { length, height, .. } => {
+                        FeatureKind::Kicker { length, height }
+                    }
+                    TrackFeature::Obstacle { width, height, .. } => {
+                        FeatureKind::Block { width, height }
+                    }
+                    TrackFeature::Gap { .. }
+                    | TrackFeature::Checkpoint { .. }
+                    | TrackFeature::Seesaw { .. }
+                    | TrackFeature::Bridge { .. }
+                    | TrackFeature::Platform { .. } => return None,
+                };
+
+                Some(PlacedFeature {
+                    x: feature.x(),
+                    kind,
+                })
+            })
+            .collect()
+    }
+
+    fn contraptions(&self, start: f64, end: f64) -> Vec<PlacedContraption> {
+        self.features
+            .iter()
+            .filter(|feature| (start..end).contains(&feature.x()))
+            .filter_map(|feature| {
+                let kind = match *feature {
+                    TrackFeature::Seesaw { length, .. } => ContraptionKind::Seesaw { length },
+                    TrackFeature::Bridge { span, .. } => ContraptionKind::Bridge {
+                        span,
+                        planks: (span / BRIDGE_PLANK_LENGTH).ceil().max(1.0) as u32,
+                    },
+                    TrackFeature::Platform {
+                        width,
+                        travel_x,
+                        travel_y,
+                        period,
+                        ..
+                    } => ContraptionKind::Platform {
+                        width,
+                        travel: DVec2::new(travel_x, travel_y),
+                        period,
+                    },
+                    _ => return None,
+                };
+
+                Some(PlacedContraption {
+                    x: feature.x(),
+                    kind,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Track, TrackError, TrackFeature};
+    use crate::course::resources::Course;
+
+    fn flat_with(feature: TrackFeature) -> Track {
+        let mut track = Track::flat(0.0, 0.0, 1000.0);
+        track.features.push(feature);
+        track
+    }
+
+    #[test]
+    fn flat_track_is_valid() {
+        assert!(Track::flat(0.0, 0.0, 1000.0).validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_too_few_control_points() {
+        let mut track = Track::flat(0.0, 0.0, 1000.0);
+        track.control_points.pop();
+
+        assert!(matches!(
+            track.validate(),
+            Err(TrackError::TooFewControlPoints)
+        ));
+    }
+
+    #[test]
+    fn rejects_non_finite_control_points() {
+        let mut track = Track::flat(0.0, 0.0, 1000.0);
+        track.control_points[1].height = f64::NAN;
+
+        assert!(matches!(track.validate(), Err(TrackError::Invalid(_))));
+    }
+
+    #[test]
+    fn rejects_control_points_on_top_of_each_other() {
+        let mut track = Track::flat(0.0, 0.0, 1000.0);
+        track.control_points[1].x = track.control_points[0].x;
+
+        assert!(matches!(track.validate(), Err(TrackError::Invalid(_))));
+    }
+
+    #[test]
+    fn rejects_negative_and_zero_feature_sizes() {
+        for feature in [
+            TrackFeature::Gap {
+                x: 0.0,
+                width: -10.0,
+            },
+            TrackFeature::Ramp {
+                x: 0.0,
+                length: 100.0,
+                height: 0.0,
+            },
+            TrackFeature::Obstacle {
+                x: 0.0,
+                width: f64::NAN,
+                height: 10.0,
+            },
+            TrackFeature::Seesaw {
+                x: 0.0,
+                length: 0.0,
+            },
+            TrackFeature::Bridge {
+                x: 0.0,
+                span: -50.0,
+            },
+        ] {
+            assert!(
+                matches!(flat_with(feature).validate(), Err(TrackError::Invalid(_))),
+                "{:?} should be rejected",
+                feature
+            );
+        }
+    }
+
+    #[test]
+    fn accepts_features_of_the_minimum_size() {
+        let track = flat_with(TrackFeature::Obstacle {
+            x: 0.0,
+            width: super::MIN_FEATURE_SIZE,
+            height: super::MIN_FEATURE_SIZE,
+        });
+
+        assert!(track.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_zero_and_negative_platform_periods() {
+        for period in [0.0, -2.0, f64::NAN] {
+            let track = flat_with(TrackFeature::Platform {
+                x: 0.0,
+                width: 100.0,
+                travel_x: 0.0,
+                travel_y: 50.0,
+                period,
+            });
+
+            assert!(
+                matches!(track.validate(), Err(TrackError::Invalid(_))),
+                "period {} should be rejected",
+                period
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_a_course_finishing_before_it_starts() {
+        let mut track = Track::flat(0.0, 0.0, 1000.0);
+        for (start, finish) in [(500.0, 100.0), (300.0, 300.0), (f64::NAN, 300.0)] {
+            track.course = Some(Course { start, finish });
+
+            assert!(
+                matches!(track.validate(), Err(TrackError::Invalid(_))),
+                "course {}..{} should be rejected",
+                start,
+                finish
+            );
+        }
+
+        track.course = Some(Course {
+            start: 100.0,
+            finish: 900.0,
+        });
+        assert!(track.validate().is_ok());
+    }
+}
@@ -0,0 +1,7 @@
+pub mod components;
+pub mod editor;
+pub mod events;
+pub mod format;
+pub mod plugin;
+pub mod resources;
+pub mod systems;
@@ -0,0 +1,37 @@
+use bevy::prelude::*;
+
+use crate::{world::plugin::WorldTerrainPlugin, GameState};
+
+use super::{
+    events::{CheckpointReachedEvent, TrackEvent},
+    resources::{ActiveTrack, TrackEditor},
+};
+
+pub struct TrackPlugin;
+
+impl Plugin for TrackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveTrack>()
+            .init_resource::<TrackEditor>()
+            .add_event::<TrackEvent>()
+            .add_event::<CheckpointReachedEvent>()
+            .add_systems(
+                Update,
+                (
+                    TrackPlugin::handle_track_event,
+                    TrackPlugin::edit_track_with_pointer,
+                    TrackPlugin::spawn_track_props,
+                )
+                    .chain()
+                    .before(WorldTerrainPlugin::rebuild_terrain_generator),
+            )
+            .add_systems(
+                Update,
+                (
+                    TrackPlugin::draw_track_editor,
+                    TrackPlugin::detect_checkpoints,
+                )
+                    .run_if(in_state(GameState::Ready)),
+            );
+    }
+}
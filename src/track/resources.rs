@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use bevy::prelude::Resource;
+use strum_macros::EnumIter;
+
+use crate::world::generators::TerrainGenerator;
+
+use super::format::{Track, TrackFeature, TRACK_EXTENSION};
+
+// Track the terrain follows instead of the procedural generators, if one is open.
+#[derive(Resource, Default)]
+pub struct ActiveTrack {
+    pub track: Option<Arc<Track>>,
+    pub error: Option<String>,
+}
+
+impl ActiveTrack {
+    pub fn generator(&self) -> Option<Arc<dyn TerrainGenerator>> {
+        self.track
+            .clone()
+            .map(|track| track as Arc<dyn TerrainGenerator>)
+    }
+}
+
+#[derive(Debug, EnumIter, PartialEq, Eq, Clone, Copy)]
+pub enum EditorTool {
+    ControlPoint,
+    Ramp,
+    Gap,
+    Obstacle,
+    Checkpoint,
+    Seesaw,
+    Bridge,
+    Platform,
+}
+
+impl EditorTool {
+    // Feature placed by clicking with this tool, sized relative to a wheel
+    pub fn feature_at(&self, x: f64) -> Option<TrackFeature> {
+        match self {
+            EditorTool::ControlPoint => None,
+            EditorTool::Ramp => Some(TrackFeature::Ramp {
+                x,
+                length: 120.0,
+                height: 40.0,
+            }),
+            EditorTool::Gap => Some(TrackFeature::Gap { x, width: 250.0 }),
+            EditorTool::Obstacle => Some(TrackFeature::Obstacle {
+                x,
+                width: 30.0,
+                height: 20.0,
+            }),
+            EditorTool::Checkpoint => Some(TrackFeature::Checkpoint { x }),
+            EditorTool::Seesaw => Some(TrackFeature::Seesaw { x, length: 200.0 }),
+            EditorTool::Bridge => Some(TrackFeature::Bridge { x, span: 300.0 }),
+            EditorTool::Platform => Some(TrackFeature::Platform {
+                x,
+                width: 120.0,
+                travel_x: 0.0,
+                travel_y: 150.0,
+                period: 6.0,
+            }),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct TrackEditor {
+    pub enabled: bool,
+    pub tool: EditorTool,
+    // Index of the selected control point
+    pub selected: Option<usize>,
+    pub dragging: bool,
+    pub path: String,
+    // Edits to a value still being dragged or typed in the editor window,
+    // applied to the active track once it's let go
+    pub draft: Option<Track>,
+}
+
+impl Default for TrackEditor {
+    fn default() -> Self {
+        TrackEditor {
+            enabled: false,
+            tool: EditorTool::ControlPoint,
+            selected: None,
+            dragging: false,
+            path: format!("tracks/untitled.{}", TRACK_EXTENSION),
+            draft: None,
+        }
+    }
+}
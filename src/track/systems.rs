@@ -0,0 +1,164 @@
+use std::sync::Arc;
+
+use avian2d::prelude::*;
+use bevy::{color::palettes::css::YELLOW, prelude::*};
+
+use crate::{
+    bicycle::frame::BicycleFrame,
+    camera::components::FollowCamera,
+    world::{plugin::WorldTerrainPlugin, terrain_query::TerrainQuery},
+    GameLayer,
+};
+
+use super::{
+    components::{Checkpoint, TrackProps},
+    events::{CheckpointReachedEvent, TrackEvent},
+    format::Track,
+    plugin::TrackPlugin,
+    resources::{ActiveTrack, TrackEditor},
+};
+
+const CHECKPOINT_WIDTH: f32 = 8.0;
+const CHECKPOINT_HEIGHT: f32 = 400.0;
+// In front of the terrain chunks
+const PROP_Z: f32 = 11.0;
+
+impl TrackPlugin {
+    pub fn handle_track_event(
+        mut events: EventReader<TrackEvent>,
+        mut active_track: ResMut<ActiveTrack>,
+        mut editor: ResMut<TrackEditor>,
+        camera: Query<&Transform, With<FollowCamera>>,
+        terrain: TerrainQuery,
+    ) {
+        for evt in events.read() {
+            match evt {
+                TrackEvent::New => {
+                    // Start level with the terrain under the camera
+                    let x = camera
+                        .get_single()
+                        .map(|camera_t| camera_t.translation.x as f64)
+                        .unwrap_or_default();
+                    active_track.track = Some(Arc::new(Track::flat(
+                        x,
+                        terrain.height(x),
+                        4.0 * WorldTerrainPlugin::CHUNK_WIDTH as f64,
+                    )));
+                    active_track.error = None;
+                }
+                TrackEvent::Load(path) => match Track::load(path) {
+                    Ok(track) => {
+                        info!("Loaded track {:?} from {:?}", track.name, path);
+                        active_track.track = Some(Arc::new(track));
+                        active_track.error = None;
+                    }
+                    Err(err) => {
+                        error!("Failed to load track {:?}: {}", path, err);
+                        // A failed load leaves the terrain as it was, so don't trigger a rebuild
+                        active_track.bypass_change_detection().error = Some(format!("{}", err));
+                    }
+                },
+                TrackEvent::Save(path) => {
+                    let Some(track) = &active_track.track else {
+                        continue;
+                    };
+
+                    let result = track.save(path);
+                    let active_track = active_track.bypass_change_detection();
+
+                    match result {
+                        Ok(()) => {
+                            info!("Saved track {:?} to {:?}", track.name, path);
+                            active_track.error = None;
+                        }
+                        Err(err) => {
+                            error!("Failed to save track {:?}: {}", path, err);
+                            active_track.error = Some(format!("{}", err));
+                        }
+                    }
+                }
+                TrackEvent::Close => {
+                    active_track.track = None;
+                    active_track.error = None;
+                }
+            }
+
+            editor.selected = None;
+            editor.dragging = false;
+            editor.draft = None;
+        }
+    }
+
+    pub fn spawn_track_props(
+        mut commands: Commands,
+        active_track: Res<ActiveTrack>,
+        track_props: Query<Entity, With<TrackProps>>,
+        mut meshes: ResMut<Assets<Mesh>>,
+        mut materials: ResMut<Assets<StandardMaterial>>,
+    ) {
+        if !active_track.is_changed() {
+            return;
+        }
+
+        for props in track_props.iter() {
+            commands.entity(props).despawn_recursive();
+        }
+
+        let Some(track) = &active_track.track else {
+            return;
+        };
+
+        let checkpoint_material = materials.add(StandardMaterial {
+            base_color: YELLOW.into(),
+            unlit: true,
+            ..Default::default()
+        });
+
+        // Ramps and obstacles are spawned with the terrain chunks they sit on,
+        // and gaps are holes in the terrain itself
+        commands
+            .spawn((
+                TrackProps,
+                Name::new("Track Props"),
+                Transform::default(),
+                Visibility::default(),
+            ))
+            .with_children(|props| {
+                for (checkpoint_index, x) in track.checkpoints().into_iter().enumerate() {
+                    props.spawn((
+                        Name::new(format!("Checkpoint({})", checkpoint_index)),
+                        Checkpoint(checkpoint_index),
+                        RigidBody::Static,
+                        Sensor,
+                        Collider::rectangle(CHECKPOINT_WIDTH as f64, CHECKPOINT_HEIGHT as f64),
+                        CollisionLayers::new([GameLayer::World], [GameLayer::Frame]),
+                        CollidingEntities::default(),
+                        Mesh3d(meshes.add(Rectangle::new(CHECKPOINT_WIDTH, CHECKPOINT_HEIGHT))),
+                        MeshMaterial3d(checkpoint_material.clone()),
+                        Transform::from_xyz(
+                            x as f32,
+                            track.ground_height(x) as f32 + CHECKPOINT_HEIGHT / 2.0,
+                            PROP_Z,
+                        ),
+                    ));
+                }
+            });
+    }
+
+    pub fn detect_checkpoints(
+        checkpoints: Query<(&Checkpoint, &CollidingEntities), Changed<CollidingEntities>>,
+        frame: Query<Entity, With<BicycleFrame>>,
+        mut reached_events: EventWriter<CheckpointReachedEvent>,
+    ) {
+        let Ok(frame) = frame.get_single() else {
+            return;
+        };
+
+        for (checkpoint, colliding) in checkpoints.iter() {
+            if colliding.contains(&frame) {
+                info!("Reached Checkpoint {}", checkpoint.0);
+                reached_events.send(CheckpointReachedEvent(checkpoint.0));
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    course::{
+        ghost::GhostReplay,
+        resources::{ActiveCourse, CourseRun, CourseSettings, TimeTrial},
+    },
+    track::resources::ActiveTrack,
+    world::elevation_profile::UNITS_PER_METER,
+};
+
+use super::plugin::UIPlugin;
+
+#[derive(Resource)]
+pub struct CourseWindowState {
+    pub open: bool,
+}
+
+impl Default for CourseWindowState {
+    fn default() -> Self {
+        CourseWindowState { open: true }
+    }
+}
+
+// Time against the personal best's, green when ahead
+fn delta_label(ui: &mut egui::Ui, time: f64, best: Option<f64>) {
+    let Some(best) = best else {
+        ui.label("");
+        return;
+    };
+    let delta = time - best;
+    let color = if delta <= 0.0 {
+        egui::Color32::GREEN
+    } else {
+        egui::Color32::RED
+    };
+    ui.colored_label(color, format!("{:+.3}", delta));
+}
+
+impl UIPlugin {
+    pub fn course_window_ui(
+        mut contexts: EguiContexts,
+        mut window_state: ResMut<CourseWindowState>,
+        mut settings: ResMut<CourseSettings>,
+        active_course: Res<ActiveCourse>,
+        active_track: Res<ActiveTrack>,
+        run: Res<CourseRun>,
+        mut time_trial: ResMut<TimeTrial>,
+        mut ghost_replay: ResMut<GhostReplay>,
+        time: Res<Time>,
+    ) {
+        let mut open = window_state.open;
+
+        // Edit a copy so the terrain is only rebuilt when a value changes
+        let mut edited = settings.clone();
+
+        egui::Window::new("Course")
+            .open(&mut open)
+            // Below the camera information
+            .default_pos(egui::pos2(4.0, 160.0))
+            .auto_sized()
+            .show(contexts.ctx_mut(), |ui| {
+                if active_track.track.is_some() {
+                    ui.label("The open track sets the course.");
+                } else {
+                    ui.checkbox(&mut edited.procedural, "Walled course");
+                    ui.add_enabled(
+                        edited.procedural,
+                        egui::Slider::new(&mut edited.length, 100.0..=5000.0)
+                            .logarithmic(true)
+                            .text("Length (m)"),
+                    );
+                }
+
+                let mut time_trial_enabled = time_trial.enabled;
+                ui.checkbox(&mut time_trial_enabled, "Time Trial");
+                if time_trial_enabled != time_trial.enabled {
+                    time_trial.enabled = time_trial_enabled;
+                    // A time trial needs somewhere to finish
+                    if time_trial_enabled && active_course.course.is_none() {
+                        edited.procedural = true;
+                    }
+                }
+
+                let Some(course) = active_course.course else {
+                    ui.label("No course, the terrain goes on forever.");
+                    return;
+                };
+
+                ui.separator();
+                ui.label(format!(
+                    "Start to finish: {:.0} m",
+                    course.length() / UNITS_PER_METER
+                ));
+
+                match (&run.result, run.elapsed(time.elapsed_secs_f64())) {
+                    (Some(result), _) => {
+                        ui.heading(format!("Finished: {:.3}s", result.elapsed));
+                        ui.label(format!(
+                            "Distance: {:.0} m",
+                            result.stats.odometer.distance / UNITS_PER_METER
+                        ));
+                        ui.label(format!(
+                            "Climbing: {:.0} m",
+                            result.stats.odometer.elevation_gain / UNITS_PER_METER
+                        ));
+                        ui.label(format!("Max Speed: {:.1}", result.stats.max_speed));
+                        ui.label(format!(
+                            "Avg Speed: {:.1}",
+                            result.stats.average_speed(result.elapsed)
+                        ));
+                        ui.label(format!("Checkpoints: {}", result.stats.checkpoints));
+                        ui.label("Ride back behind the start gate to go again.");
+                    }
+                    (None, Some(elapsed)) => {
+                        ui.heading(format!("{:.1}s", elapsed));
+                    }
+                    (None, None) => {
+                        ui.label("Ride through the start gate to begin.");
+                    }
+                }
+
+                if time_trial.enabled {
+                    Self::time_trial_ui(ui, &time_trial, &run, active_course.splits.len());
+
+                    // The ghost is loaded as a run starts, so switching on waits for the next
+                    let mut ghost_enabled = ghost_replay.enabled;
+                    ui.checkbox(&mut ghost_enabled, "Ghost of best run");
+                    if ghost_enabled != ghost_replay.enabled {
+                        ghost_replay.enabled = ghost_enabled;
+                    }
+                    if let Some(error) = &ghost_replay.error {
+                        ui.colored_label(egui::Color32::RED, error);
+                    }
+                }
+            });
+
+        settings.set_if_neq(edited);
+        window_state.open = open;
+    }
+
+    fn time_trial_ui(ui: &mut egui::Ui, time_trial: &TimeTrial, run: &CourseRun, splits: usize) {
+        ui.separator();
+        ui.heading("Time Trial");
+
+        if let Some(course_id) = &time_trial.course_id {
+            ui.label(course_id);
+        }
+        ui.label(&time_trial.bike_id);
+
+        if let Some(error) = &time_trial.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
+        match time_trial.personal_best() {
+            Some(best) => ui.label(format!(
+                "Personal best: {:.3}s ({} attempts)",
+                best.elapsed,
+                time_trial.attempts()
+            )),
+            None => ui.label("No personal best yet"),
+        };
+        if run.result.is_some() && time_trial.new_best {
+            ui.colored_label(egui::Color32::GOLD, "New personal best!");
+        }
+
+        // Deltas are to the best as it stood when the run started
+        let comparison = time_trial.comparison.as_ref();
+
+        // The finished run's splits, or the current run's so far
+        let (run_splits, finish) = match &run.result {
+            Some(result) => (&result.splits, Some(result.elapsed)),
+            None => (&run.splits, None),
+        };
+
+        // Between splits, against the best at the same point on the course
+        if let Some((time, best)) = comparison
+            .filter(|_| run.is_running())
+            .and_then(|comparison| run.progress_against(comparison))
+        {
+            ui.horizontal(|ui| {
+                ui.label("Live:");
+                delta_label(ui, time, Some(best));
+            });
+        }
+
+        egui::Grid::new("Splits").striped(true).show(ui, |ui| {
+            for split in 0..splits {
+                ui.label(format!("Split {}", split + 1));
+                match run_splits.get(split) {
+                    Some(time) => {
+                        ui.label(format!("{:.3}s", time));
+                        delta_label(
+                            ui,
+                            *time,
+                            comparison.and_then(|best| best.splits.get(split).copied()),
+                        );
+                    }
+                    None => {
+                        ui.label("-");
+                        ui.label("");
+                    }
+                }
+                ui.end_row();
+            }
+
+            ui.label("Finish");
+            match finish {
+                Some(time) => {
+                    ui.label(format!("{:.3}s", time));
+                    delta_label(ui, time, comparison.map(|best| best.elapsed));
+                }
+                None => {
+                    ui.label("-");
+                    ui.label("");
+                }
+            }
+            ui.end_row();
+        });
+    }
+}
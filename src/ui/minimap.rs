@@ -0,0 +1,231 @@
+use avian2d::prelude::Position;
+use bevy::prelude::*;
+use bevy_egui::{
+    egui::{self, Align2, Color32, FontId, Sense, Stroke},
+    EguiContexts,
+};
+
+use crate::{
+    bicycle::frame::BicycleFrame,
+    camera::components::FollowCamera,
+    course::resources::ActiveCourse,
+    track::resources::ActiveTrack,
+    world::{
+        elevation_profile::UNITS_PER_METER, plugin::WorldTerrainPlugin, terrain_query::TerrainQuery,
+    },
+};
+
+use super::plugin::UIPlugin;
+
+// Points along the profile line, regardless of how far it reaches
+const MINIMAP_SAMPLES: usize = 240;
+// How far ahead the upcoming grade is measured
+const GRADE_LOOKAHEAD: f64 = 50.0 * UNITS_PER_METER;
+// Grades above this are coloured as a climb (or descent)
+const STEEP_GRADE: f64 = 6.0;
+
+#[derive(Resource)]
+pub struct MinimapState {
+    pub open: bool,
+    chunks_behind: u8,
+    chunks_ahead: u8,
+}
+
+impl Default for MinimapState {
+    fn default() -> Self {
+        MinimapState {
+            open: true,
+            chunks_behind: 1,
+            chunks_ahead: 4,
+        }
+    }
+}
+
+fn grade_color(grade: f64) -> Color32 {
+    if grade > STEEP_GRADE {
+        Color32::from_rgb(230, 85, 13)
+    } else if grade < -STEEP_GRADE {
+        Color32::from_rgb(49, 130, 189)
+    } else {
+        Color32::from_rgb(49, 163, 84)
+    }
+}
+
+impl UIPlugin {
+    pub fn minimap_window_ui(
+        mut contexts: EguiContexts,
+        mut minimap_state: ResMut<MinimapState>,
+        frame: Query<&Position, With<BicycleFrame>>,
+        camera: Query<&Transform, With<FollowCamera>>,
+        active_track: Res<ActiveTrack>,
+        active_course: Res<ActiveCourse>,
+        terrain: TerrainQuery,
+    ) {
+        let minimap_state = minimap_state.as_mut();
+        if !minimap_state.open {
+            return;
+        }
+
+        // Follow the bicycle, or the camera before it spawns
+        let bike_x = match (frame.get_single(), camera.get_single()) {
+            (Ok(position), _) => position.x,
+            (_, Ok(camera_t)) => camera_t.translation.x as f64,
+            _ => return,
+        };
+
+        let chunk_width = WorldTerrainPlugin::CHUNK_WIDTH as f64;
+        let start = bike_x - minimap_state.chunks_behind as f64 * chunk_width;
+        let end = bike_x + minimap_state.chunks_ahead as f64 * chunk_width;
+
+        let samples: Vec<(f64, Option<f64>)> = (0..=MINIMAP_SAMPLES)
+            .map(|i| {
+                let x = start + (end - start) * i as f64 / MINIMAP_SAMPLES as f64;
+                let sample = terrain.sample(x);
+                (x, (!sample.is_gap()).then_some(sample.height))
+            })
+            .collect();
+
+        let ahead = terrain.height(bike_x + GRADE_LOOKAHEAD);
+        let average_grade = 100.0 * (ahead - terrain.height(bike_x)) / GRADE_LOOKAHEAD;
+        let steepest_grade = samples
+            .iter()
+            .map(|(x, _)| *x)
+            .filter(|x| (bike_x..=bike_x + GRADE_LOOKAHEAD).contains(x))
+            .chain([bike_x])
+            .map(|x| terrain.grade(x))
+            .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+            .unwrap_or_default();
+
+        let checkpoints = active_track
+            .track
+            .as_ref()
+            .map(|track| track.checkpoints())
+            .unwrap_or_default();
+
+        egui::Window::new("Elevation Minimap")
+            .anchor(Align2::CENTER_TOP, egui::Vec2::new(0.0, 28.0))
+            .title_bar(false)
+            .default_width(420.0)
+            .resizable(false)
+            .show(contexts.ctx_mut(), |ui| {
+                ui.colored_label(
+                    grade_color(average_grade),
+                    format!(
+                        "Next {:.0} m: {:+.1}% avg, {:+.1}% steepest",
+                        GRADE_LOOKAHEAD / UNITS_PER_METER,
+                        average_grade,
+                        steepest_grade
+                    ),
+                );
+
+                let (response, painter) =
+                    ui.allocate_painter(egui::vec2(420.0, 70.0), Sense::hover());
+                let rect = response.rect;
+                painter.rect_stroke(rect, 2.0, Stroke::new(1.0, Color32::DARK_GRAY));
+
+                let heights = samples.iter().filter_map(|(_, height)| *height);
+                let min = heights.clone().fold(f64::INFINITY, f64::min);
+                let max = heights.fold(f64::NEG_INFINITY, f64::max);
+                if min.is_finite() {
+                    let range = (max - min).max(f64::EPSILON);
+
+                    let to_screen = |x: f64, height: f64| {
+                        egui::pos2(
+                            rect.left() + ((x - start) / (end - start)) as f32 * rect.width(),
+                            rect.bottom() - ((height - min) / range) as f32 * rect.height(),
+                        )
+                    };
+
+                    // Gaps leave holes in the profile
+                    for segment in samples.windows(2) {
+                        let [(left_x, Some(left)), (right_x, Some(right))] = *segment else {
+                            continue;
+                        };
+                        let grade = 100.0 * (right - left) / (right_x - left_x);
+
+                        painter.line_segment(
+                            [to_screen(left_x, left), to_screen(right_x, right)],
+                            Stroke::new(1.5, grade_color(grade)),
+                        );
+                    }
+
+                    for checkpoint in checkpoints.iter().filter(|x| (start..=end).contains(*x)) {
+                        let top = to_screen(*checkpoint, max);
+                        painter.line_segment(
+                            [top, egui::pos2(top.x, rect.bottom())],
+                            Stroke::new(1.0, Color32::YELLOW),
+                        );
+                    }
+
+                    // Start gate, split lines and finish line, drawn full height
+                    let course_lines = active_course
+                        .course
+                        .iter()
+                        .flat_map(|course| {
+                            [
+                                (course.start, Color32::GREEN, 2.0),
+                                (course.finish, Color32::RED, 2.0),
+                            ]
+                        })
+                        .chain(
+                            active_course
+                                .splits
+                                .iter()
+                                .map(|split| (*split, Color32::LIGHT_BLUE, 1.0)),
+                        );
+                    for (x, color, width) in
+                        course_lines.filter(|(x, _, _)| (start..=end).contains(x))
+                    {
+                        let x = to_screen(x, min).x;
+                        painter.line_segment(
+                            [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+                            Stroke::new(width, color),
+                        );
+                    }
+
+                    let bike = to_screen(bike_x, terrain.height(bike_x));
+                    painter.line_segment(
+                        [
+                            egui::pos2(bike.x, rect.top()),
+                            egui::pos2(bike.x, rect.bottom()),
+                        ],
+                        Stroke::new(1.0, Color32::WHITE),
+                    );
+                    painter.circle_filled(bike, 3.0, Color32::WHITE);
+
+                    painter.text(
+                        rect.left_top(),
+                        Align2::LEFT_TOP,
+                        format!("{:.0} m", max / UNITS_PER_METER),
+                        FontId::monospace(10.0),
+                        Color32::GRAY,
+                    );
+                    painter.text(
+                        rect.left_bottom(),
+                        Align2::LEFT_BOTTOM,
+                        format!("{:.0} m", min / UNITS_PER_METER),
+                        FontId::monospace(10.0),
+                        Color32::GRAY,
+                    );
+                } else {
+                    // Keep the window, and its sliders, while only a gap is in view
+                    painter.text(
+                        rect.center(),
+                        Align2::CENTER_CENTER,
+                        "No ground in view",
+                        FontId::proportional(12.0),
+                        Color32::GRAY,
+                    );
+                }
+
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::Slider::new(&mut minimap_state.chunks_behind, 0..=4).text("Behind"),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut minimap_state.chunks_ahead, 1..=16).text("Ahead"),
+                    );
+                });
+            });
+    }
+}
@@ -1,2 +1,6 @@
+pub mod course;
+pub mod minimap;
+pub mod plots;
 pub mod plugin;
 pub mod systems;
+pub mod track_editor;
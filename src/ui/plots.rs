@@ -0,0 +1,240 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use bevy_egui::{
+    egui::{self, Align2, Color32, FontId, Sense, Stroke},
+    EguiContexts,
+};
+use strum::IntoEnumIterator;
+
+use crate::bounded_queue::BoundedQueue;
+
+use super::{
+    plugin::UIPlugin,
+    systems::{BicycleStat, BicycleStats, BICYCLE_STAT_SAMPLES},
+};
+
+#[derive(Resource)]
+pub struct StatPlotState {
+    pub open: bool,
+    selected: HashSet<BicycleStat>,
+    window: usize,
+    percentile: f64,
+    // Frozen copy of the sample history while paused
+    snapshot: Option<HashMap<BicycleStat, BoundedQueue<f64>>>,
+    // Samples back from the newest sample, only used while paused
+    scrub: usize,
+}
+
+impl Default for StatPlotState {
+    fn default() -> Self {
+        StatPlotState {
+            open: false,
+            selected: [BicycleStat::Speed, BicycleStat::CrankPower]
+                .into_iter()
+                .collect(),
+            window: 300,
+            percentile: 95.0,
+            snapshot: None,
+            scrub: 0,
+        }
+    }
+}
+
+// Linearly interpolated percentile, selecting the two ranks instead of sorting
+fn window_percentile(samples: &[f64], percentile: f64) -> Option<f64> {
+    if samples.is_empty() {
+        return None;
+    }
+
+    let mut scratch = samples.to_vec();
+    let rank = (percentile / 100.0) * (scratch.len() - 1) as f64;
+    let lower_rank = rank.floor() as usize;
+
+    let (_, &mut lower, above) = scratch.select_nth_unstable_by(lower_rank, f64::total_cmp);
+    let upper = if rank.fract() > 0.0 {
+        above
+            .iter()
+            .copied()
+            .min_by(f64::total_cmp)
+            .unwrap_or(lower)
+    } else {
+        lower
+    };
+
+    Some(lower + (upper - lower) * rank.fract())
+}
+
+fn series_color(stat: &BicycleStat) -> Color32 {
+    let palette = [
+        Color32::from_rgb(230, 85, 13),
+        Color32::from_rgb(49, 130, 189),
+        Color32::from_rgb(49, 163, 84),
+        Color32::from_rgb(204, 76, 2),
+        Color32::from_rgb(117, 107, 177),
+        Color32::from_rgb(221, 52, 151),
+    ];
+    let index = BicycleStat::iter().position(|s| &s == stat).unwrap_or(0);
+    palette[index % palette.len()]
+}
+
+impl UIPlugin {
+    pub fn stat_plot_window_ui(
+        mut contexts: EguiContexts,
+        mut plot_state: ResMut<StatPlotState>,
+        bicycle_stats: Res<BicycleStats>,
+    ) {
+        let plot_state = plot_state.as_mut();
+        let mut open = plot_state.open;
+
+        egui::Window::new("Statistics Plots")
+            .open(&mut open)
+            .default_width(480.0)
+            .show(contexts.ctx_mut(), |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for stat in BicycleStat::iter() {
+                        let mut selected = plot_state.selected.contains(&stat);
+                        if ui.checkbox(&mut selected, format!("{:?}", stat)).changed() {
+                            if selected {
+                                plot_state.selected.insert(stat);
+                            } else {
+                                plot_state.selected.remove(&stat);
+                            }
+                        }
+                    }
+                });
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::Slider::new(&mut plot_state.window, 10..=BICYCLE_STAT_SAMPLES)
+                            .text("Window"),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut plot_state.percentile, 0.0..=100.0)
+                            .text("Percentile"),
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    let mut paused = plot_state.snapshot.is_some();
+                    if ui.toggle_value(&mut paused, "Pause").changed() {
+                        plot_state.snapshot = paused.then(|| {
+                            BicycleStat::iter()
+                                .map(|stat| (stat, bicycle_stats.get(&stat).clone()))
+                                .collect()
+                        });
+                        plot_state.scrub = 0;
+                    }
+
+                    let history = plot_state
+                        .snapshot
+                        .as_ref()
+                        .and_then(|snapshot| snapshot.values().map(BoundedQueue::len).max())
+                        .unwrap_or(0);
+                    ui.add_enabled(
+                        paused,
+                        egui::Slider::new(
+                            &mut plot_state.scrub,
+                            0..=history.saturating_sub(plot_state.window),
+                        )
+                        .text("Scrub"),
+                    );
+                });
+
+                ui.separator();
+
+                for stat in BicycleStat::iter().filter(|s| plot_state.selected.contains(s)) {
+                    let queue = match &plot_state.snapshot {
+                        Some(snapshot) => &snapshot[&stat],
+                        None => bicycle_stats.get(&stat),
+                    };
+
+                    let end = queue.len().saturating_sub(plot_state.scrub);
+                    let start = end.saturating_sub(plot_state.window);
+                    let window: Vec<f64> = queue
+                        .iter()
+                        .skip(start)
+                        .take(end - start)
+                        .copied()
+                        .collect();
+
+                    Self::stat_plot(ui, &stat, queue, &window, plot_state.percentile);
+                }
+            });
+
+        plot_state.open = open;
+    }
+
+    // The summary and vertical scale come from the queue's running statistics
+    // over the whole history; only the percentile is taken over the window
+    fn stat_plot(
+        ui: &mut egui::Ui,
+        stat: &BicycleStat,
+        queue: &BoundedQueue<f64>,
+        samples: &[f64],
+        percentile: f64,
+    ) {
+        let (Some(min), Some(max), Some(window_percentile)) = (
+            queue.min(),
+            queue.max(),
+            window_percentile(samples, percentile),
+        ) else {
+            ui.label(format!("{:?}: no samples", stat));
+            return;
+        };
+
+        ui.label(format!(
+            "{:?}  min {:.1}  max {:.1}  avg {:.1}  sd {:.1}  ema {:.1}  p{:.0} {:.1}",
+            stat,
+            min,
+            max,
+            queue.mean().unwrap_or_default(),
+            queue.std_dev().unwrap_or_default(),
+            queue.ema().unwrap_or_default(),
+            percentile,
+            window_percentile
+        ));
+
+        let (response, painter) =
+            ui.allocate_painter(egui::vec2(ui.available_width(), 80.0), Sense::hover());
+        let rect = response.rect;
+
+        painter.rect_stroke(rect, 2.0, Stroke::new(1.0, Color32::DARK_GRAY));
+
+        let range = (max - min).max(f64::EPSILON);
+        let step = rect.width() / (samples.len().max(2) - 1) as f32;
+        let points = samples
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let t = ((value - min) / range) as f32;
+                egui::pos2(
+                    rect.left() + step * i as f32,
+                    rect.bottom() - t * rect.height(),
+                )
+            })
+            .collect();
+
+        painter.add(egui::Shape::line(
+            points,
+            Stroke::new(1.5, series_color(stat)),
+        ));
+
+        painter.text(
+            rect.left_top(),
+            Align2::LEFT_TOP,
+            format!("{:.1}", max),
+            FontId::monospace(10.0),
+            Color32::GRAY,
+        );
+        painter.text(
+            rect.left_bottom(),
+            Align2::LEFT_BOTTOM,
+            format!("{:.1}", min),
+            FontId::monospace(10.0),
+            Color32::GRAY,
+        );
+    }
+}
@@ -3,7 +3,12 @@ use iyes_perf_ui::PerfUiPlugin;
 
 use crate::GameState;
 
-use super::systems::{BicycleStats, UiState};
+use super::{
+    course::CourseWindowState,
+    minimap::MinimapState,
+    plots::StatPlotState,
+    systems::{BicycleStats, UiState},
+};
 
 pub struct UIPlugin;
 
@@ -11,6 +16,9 @@ impl Plugin for UIPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<BicycleStats>()
             .init_resource::<UiState>()
+            .init_resource::<StatPlotState>()
+            .init_resource::<MinimapState>()
+            .init_resource::<CourseWindowState>()
             .add_plugins(PerfUiPlugin)
             .add_plugins(bevy::diagnostic::FrameTimeDiagnosticsPlugin)
             .add_systems(Startup, UIPlugin::performance_ui)
@@ -20,6 +28,13 @@ impl Plugin for UIPlugin {
                     UIPlugin::top_panel_ui,
                     UIPlugin::bottom_panel_ui,
                     UIPlugin::camera_window_ui,
+                    UIPlugin::trip_computer_window_ui,
+                    UIPlugin::terrain_window_ui,
+                    UIPlugin::environment_window_ui,
+                    UIPlugin::stat_plot_window_ui,
+                    UIPlugin::minimap_window_ui,
+                    UIPlugin::course_window_ui,
+                    UIPlugin::track_editor_window_ui,
                     UIPlugin::update_resources,
                     UIPlugin::measure_bicycle_statistics,
                 )
@@ -1,37 +1,58 @@
 use std::collections::HashMap;
 
-use avian2d::prelude::{AngularVelocity, LinearVelocity, Rotation};
+use avian2d::prelude::{AngularVelocity, ExternalTorque, LinearVelocity, Position};
 use bevy::{prelude::*, reflect::List};
 use bevy_egui::{
     egui::{self, panel::TopBottomSide, Align2},
     EguiContexts,
 };
 use iyes_perf_ui::{entry::PerfUiEntry, prelude::PerfUiDefaultEntries};
+use serde::Serialize;
 
 use crate::{
     bicycle::{
         frame::BicycleFrame,
         groupset::{CassetteRadius, ChainringRadius, Cog},
+        power::{crank_power, EnergyBudget},
+        trip::TripComputer,
         wheel::BicycleWheel,
     },
-    camera::systems::CameraState,
-    world::resources::{MaxTerrainChunkCount, TerrainSeed},
-    BoundedQueue,
+    bounded_queue::BoundedQueue,
+    camera::{components::FollowCamera, systems::CameraState},
+    environment::resources::{Weather, WeatherKind, WorldClock},
+    telemetry::resources::{TelemetryConfig, TelemetryFormat, TelemetryRecorder},
+    track::resources::TrackEditor,
+    world::{
+        elevation_profile::UNITS_PER_METER,
+        events::ElevationProfileEvent,
+        resources::{
+            ActiveTerrainGenerator, ElevationProfileSource, MaxTerrainChunkCount, TerrainProfile,
+            TerrainSeed, TerrainSettings,
+        },
+        terrain_query::TerrainQuery,
+    },
 };
 
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-use super::plugin::UIPlugin;
+use super::{
+    course::CourseWindowState, minimap::MinimapState, plots::StatPlotState, plugin::UIPlugin,
+};
 
 #[derive(Default, Resource)]
 pub struct UiState {
     chainring_radius: f32,
     cassette_radius: f32,
     max_terrain_chunk_count: u8,
+    terrain_settings: TerrainSettings,
+    show_terrain_window: bool,
+    elevation_profile_path: String,
+    seed_text: String,
+    show_environment_window: bool,
 }
 
-#[derive(Debug, EnumIter, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, EnumIter, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize)]
 pub enum BicycleStat {
     Speed,
     Grade,
@@ -39,6 +60,12 @@ pub enum BicycleStat {
     CassetteRPM,
     FrontWheelRPM,
     RearWheelRPM,
+    CrankPower,
+    KineticEnergy,
+    PotentialEnergy,
+    RollingLoss,
+    DragLoss,
+    HazardLoss,
 }
 
 #[derive(Resource)]
@@ -46,7 +73,7 @@ pub struct BicycleStats {
     stats: HashMap<BicycleStat, BoundedQueue<f64>>,
 }
 
-const BICYCLE_STAT_SAMPLES: usize = 1000;
+pub const BICYCLE_STAT_SAMPLES: usize = 1000;
 
 impl Default for BicycleStats {
     fn default() -> Self {
@@ -61,11 +88,17 @@ impl Default for BicycleStats {
 }
 
 impl BicycleStats {
+    pub fn get(&self, stat: &BicycleStat) -> &BoundedQueue<f64> {
+        self.stats.get(stat).unwrap()
+    }
+
+    // Average over the retained samples, or zero before the first sample
     pub fn get_avg(&self, stat: &BicycleStat) -> f64 {
-        let stat_samples = self.stats.get(stat).unwrap().clone();
-        let sum: f64 = stat_samples.clone().into_iter().sum();
-        let count: f64 = stat_samples.len() as f64;
-        sum / count
+        self.get(stat).mean().unwrap_or_default()
+    }
+
+    pub fn latest(&self, stat: &BicycleStat) -> Option<f64> {
+        self.get(stat).latest().copied()
     }
 
     pub fn enqueue_value_for_stat(&mut self, stat: &BicycleStat, value: f64) {
@@ -83,28 +116,240 @@ impl UIPlugin {
         mut chainring_radius: ResMut<ChainringRadius>,
         mut cassette_radius: ResMut<CassetteRadius>,
         mut max_terrain_chunk_count: ResMut<MaxTerrainChunkCount>,
+        mut terrain_settings: ResMut<TerrainSettings>,
     ) {
         if ui_state.is_changed() && !ui_state.is_added() {
             let _ = chainring_radius.replace_if_neq(ChainringRadius(ui_state.chainring_radius));
             let _ = cassette_radius.replace_if_neq(CassetteRadius(ui_state.cassette_radius));
             let _ = max_terrain_chunk_count
                 .replace_if_neq(MaxTerrainChunkCount(ui_state.max_terrain_chunk_count));
+            let _ = terrain_settings.replace_if_neq(ui_state.terrain_settings.clone());
         }
     }
 
-    pub fn camera_window_ui(mut contexts: EguiContexts, camera_state: Res<State<CameraState>>) {
+    pub fn camera_window_ui(
+        mut contexts: EguiContexts,
+        camera_state: Res<State<CameraState>>,
+        camera: Query<&Transform, With<FollowCamera>>,
+        terrain_generator: Res<ActiveTerrainGenerator>,
+    ) {
+        let biome = camera
+            .get_single()
+            .ok()
+            .and_then(|camera_t| terrain_generator.0.biome(camera_t.translation.x as f64));
+
         egui::Window::new("Camera Information")
             .anchor(Align2::LEFT_TOP, bevy_egui::egui::Vec2::new(4.0, 28.0))
             .title_bar(false)
             .auto_sized()
             .show(contexts.ctx_mut(), |ui| {
                 ui.label(format!("Camera Mode: {:?}", camera_state));
+                if let Some(biome) = biome {
+                    ui.label(format!("Biome: {:?}", biome));
+                }
+            });
+    }
+
+    pub fn terrain_window_ui(
+        mut ui_state: ResMut<UiState>,
+        mut contexts: EguiContexts,
+        mut terrain_seed: ResMut<TerrainSeed>,
+        profile_source: Res<ElevationProfileSource>,
+        mut profile_events: EventWriter<ElevationProfileEvent>,
+    ) {
+        let ui_state = ui_state.as_mut();
+        let mut open = ui_state.show_terrain_window;
+
+        if terrain_seed.is_changed() {
+            ui_state.seed_text = terrain_seed.0.to_string();
+        }
+
+        egui::Window::new("Terrain Generator")
+            .open(&mut open)
+            .auto_sized()
+            .show(contexts.ctx_mut(), |ui| {
+                ui.heading("Seed");
+                let parsed_seed = ui_state.seed_text.trim().parse::<u32>();
+
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut ui_state.seed_text);
+
+                    if ui
+                        .add_enabled(parsed_seed.is_ok(), egui::Button::new("Apply"))
+                        .clicked()
+                    {
+                        if let Ok(seed) = parsed_seed {
+                            // Regenerates the terrain, unless the seed is unchanged
+                            terrain_seed.set_if_neq(TerrainSeed(seed));
+                        }
+                    }
+                    if ui.button("Random").clicked() {
+                        *terrain_seed = TerrainSeed::random();
+                    }
+                    if ui.button("Copy").clicked() {
+                        let seed = terrain_seed.0.to_string();
+                        ui.output_mut(|output| output.copied_text = seed);
+                    }
+                });
+
+                if parsed_seed.is_err() {
+                    ui.colored_label(
+                        egui::Color32::RED,
+                        "Seeds are whole numbers up to 4294967295",
+                    );
+                }
+                ui.label("Run with --seed <seed> to start on a given seed.");
+
+                ui.separator();
+                ui.heading("Elevation Profile");
+                ui.horizontal(|ui| {
+                    ui.label("GPX / CSV:");
+                    ui.text_edit_singleline(&mut ui_state.elevation_profile_path);
+
+                    if ui.button("Load").clicked() {
+                        profile_events.send(ElevationProfileEvent::Load(
+                            ui_state.elevation_profile_path.trim().into(),
+                        ));
+                    }
+                });
+
+                if let Some(error) = &profile_source.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+
+                if let Some(profile) = &profile_source.profile {
+                    ui.label(format!(
+                        "{}: {:.2} km, {:.0} m climbing, {:.0} m range",
+                        profile.name,
+                        profile.length() / 1000.0,
+                        profile.elevation_gain(),
+                        profile.elevation_range()
+                    ));
+                    ui.label(format!("1 m = {} units", UNITS_PER_METER));
+
+                    if ui.button("Return to Procedural Terrain").clicked() {
+                        profile_events.send(ElevationProfileEvent::Clear);
+                    }
+                    return;
+                }
+
+                ui.separator();
+                let settings = &mut ui_state.terrain_settings;
+
+                ui.checkbox(&mut settings.biomes, "Biomes");
+                ui.checkbox(&mut settings.features, "Rocks, Logs, Jumps and Puddles");
+
+                if settings.biomes {
+                    ui.label("Each biome picks its own profile from these base values.");
+                }
+
+                egui::ComboBox::from_label("Profile")
+                    .selected_text(format!("{:?}", settings.profile))
+                    .show_ui(ui, |ui| {
+                        for profile in TerrainProfile::iter() {
+                            ui.selectable_value(
+                                &mut settings.profile,
+                                profile,
+                                format!("{:?}", profile),
+                            );
+                        }
+                    });
+
+                ui.add(egui::Slider::new(&mut settings.octaves, 1..=8).text("Octaves"));
+                ui.add(
+                    egui::Slider::new(&mut settings.frequency, 0.00001..=0.001)
+                        .logarithmic(true)
+                        .text("Frequency"),
+                );
+                ui.add(egui::Slider::new(&mut settings.lacunarity, 1.0..=4.0).text("Lacunarity"));
+                ui.add(egui::Slider::new(&mut settings.gain, 0.0..=1.0).text("Gain"));
+                ui.add(egui::Slider::new(&mut settings.amplitude, 0.0..=1000.0).text("Amplitude"));
+            });
+
+        ui_state.show_terrain_window = open;
+    }
+
+    pub fn environment_window_ui(
+        mut ui_state: ResMut<UiState>,
+        mut contexts: EguiContexts,
+        mut clock: ResMut<WorldClock>,
+        mut weather: ResMut<Weather>,
+    ) {
+        let mut open = ui_state.show_environment_window;
+
+        // Edit a copy so the terrain friction is only reapplied when the weather changes
+        let mut edited = weather.clone();
+
+        egui::Window::new("Environment")
+            .open(&mut open)
+            .auto_sized()
+            .show(contexts.ctx_mut(), |ui| {
+                ui.heading("Time of Day");
+                ui.label(format!(
+                    "{:02}:{:02}",
+                    clock.hour as u32,
+                    (clock.hour.fract() * 60.0) as u32
+                ));
+                ui.add(egui::Slider::new(&mut clock.hour, 0.0..=23.99).text("Hour"));
+                ui.add(
+                    egui::Slider::new(&mut clock.day_length, 60.0..=3600.0)
+                        .logarithmic(true)
+                        .text("Day Length (s)"),
+                );
+                ui.checkbox(&mut clock.paused, "Pause Clock");
+
+                ui.separator();
+                ui.heading("Weather");
+                ui.horizontal(|ui| {
+                    for kind in WeatherKind::iter() {
+                        ui.selectable_value(&mut edited.kind, kind, format!("{:?}", kind));
+                    }
+                });
+                ui.add(egui::Slider::new(&mut edited.intensity, 0.0..=1.0).text("Intensity"));
+                ui.add(egui::Slider::new(&mut edited.wind, -200.0..=200.0).text("Wind"));
+                ui.label(format!(
+                    "Wind: {:.0} m/s {}",
+                    edited.wind.abs() / UNITS_PER_METER,
+                    if edited.wind < 0.0 { "head" } else { "tail" }
+                ));
+            });
+
+        weather.set_if_neq(edited);
+        ui_state.show_environment_window = open;
+    }
+
+    pub fn trip_computer_window_ui(mut contexts: EguiContexts, trip: Res<TripComputer>) {
+        egui::Window::new("Trip Computer")
+            .anchor(Align2::RIGHT_TOP, bevy_egui::egui::Vec2::new(-4.0, 28.0))
+            .title_bar(false)
+            .auto_sized()
+            .show(contexts.ctx_mut(), |ui| {
+                ui.heading("Trip");
+                ui.label(format!("Distance: {:.0}", trip.odometer.distance));
+                ui.label(format!(
+                    "Elevation: +{:.0} / -{:.0}",
+                    trip.odometer.elevation_gain, trip.odometer.elevation_loss
+                ));
+                ui.label(format!("Max Speed: {:.1}", trip.max_speed));
+                ui.label(format!("Avg Speed: {:.1}", trip.average_speed()));
+                ui.label(format!(
+                    "Moving Time: {:.0}s / {:.0}s",
+                    trip.moving_time, trip.ride_time
+                ));
+                ui.label(format!("Avg Cadence: {:.0} RPM", trip.average_cadence()));
+                ui.label(format!("Avg Power: {:.0} W", trip.average_power()));
             });
     }
 
     pub fn top_panel_ui(
         mut ui_state: ResMut<UiState>,
         mut contexts: EguiContexts,
+        mut plot_state: ResMut<StatPlotState>,
+        mut minimap_state: ResMut<MinimapState>,
+        mut course_window_state: ResMut<CourseWindowState>,
+        mut telemetry_config: ResMut<TelemetryConfig>,
+        telemetry_recorder: Res<TelemetryRecorder>,
+        mut track_editor: ResMut<TrackEditor>,
         terrain_seed: Res<TerrainSeed>,
         _camera_state: Res<State<CameraState>>,
     ) {
@@ -122,24 +367,48 @@ impl UIPlugin {
                 );
 
                 ui.label("Terrain Chunk Count:");
+                ui.separator();
+
+                ui.toggle_value(&mut plot_state.open, "Plots");
+                ui.toggle_value(&mut minimap_state.open, "Minimap");
+                ui.toggle_value(&mut course_window_state.open, "Course");
+                ui.toggle_value(&mut ui_state.show_terrain_window, "Terrain");
+                ui.toggle_value(&mut ui_state.show_environment_window, "Environment");
+                ui.toggle_value(&mut track_editor.enabled, "Track Editor");
+                ui.separator();
+
+                ui.label("Telemetry:");
+                ui.add_enabled_ui(!telemetry_recorder.is_recording(), |ui| {
+                    ui.radio_value(&mut telemetry_config.format, TelemetryFormat::Csv, "CSV");
+                    ui.radio_value(
+                        &mut telemetry_config.format,
+                        TelemetryFormat::JsonLines,
+                        "JSONL",
+                    );
+                });
+                if telemetry_recorder.is_recording() {
+                    ui.colored_label(egui::Color32::RED, "REC");
+                }
             });
         });
     }
 
     pub fn measure_bicycle_statistics(
-        frame: Query<(&LinearVelocity, &Rotation), With<BicycleFrame>>,
+        frame: Query<(&LinearVelocity, &Position), With<BicycleFrame>>,
         wheels: Query<(Entity, &BicycleWheel, &AngularVelocity)>,
-        cogs: Query<(Entity, &Cog, &AngularVelocity)>,
+        cogs: Query<(Entity, &Cog, &AngularVelocity, &ExternalTorque)>,
+        energy_budget: Res<EnergyBudget>,
+        terrain: TerrainQuery,
         mut bicycle_stats: ResMut<BicycleStats>,
     ) {
         if wheels.is_empty() || cogs.is_empty() || frame.is_empty() {
             return;
         }
 
-        let (lin_vel, rotation) = frame.single();
+        let (lin_vel, position) = frame.single();
         bicycle_stats.enqueue_value_for_stat(&BicycleStat::Speed, lin_vel.length());
-        let grade = 100.0 * rotation.sin / rotation.cos;
-        bicycle_stats.enqueue_value_for_stat(&BicycleStat::Grade, grade);
+        // Grade of the ground below, so pitching over a bump doesn't skew it
+        bicycle_stats.enqueue_value_for_stat(&BicycleStat::Grade, terrain.grade(position.x));
 
         // Enqueue Wheel RPMs
         for (_wheel_ent, wheel, ang_vel) in wheels.iter() {
@@ -156,24 +425,40 @@ impl UIPlugin {
         }
 
         // Enqueue Cog RPMs
-        for (_, cog, ang_vel) in cogs.iter() {
+        for (_, cog, ang_vel, torque) in cogs.iter() {
             let rpm = -ang_vel.0 * 60.0 / (2.0 * std::f64::consts::PI);
 
             match cog {
                 Cog::FrontChainring => {
-                    bicycle_stats.enqueue_value_for_stat(&BicycleStat::ChainringRPM, rpm)
+                    bicycle_stats.enqueue_value_for_stat(&BicycleStat::ChainringRPM, rpm);
+                    bicycle_stats.enqueue_value_for_stat(
+                        &BicycleStat::CrankPower,
+                        crank_power(torque, ang_vel),
+                    );
                 }
                 Cog::RearCassette => {
                     bicycle_stats.enqueue_value_for_stat(&BicycleStat::CassetteRPM, rpm)
                 }
             }
         }
+
+        // Enqueue Energy Budget
+        for (stat, value) in [
+            (BicycleStat::KineticEnergy, energy_budget.kinetic),
+            (BicycleStat::PotentialEnergy, energy_budget.potential),
+            (BicycleStat::RollingLoss, energy_budget.rolling_loss),
+            (BicycleStat::DragLoss, energy_budget.drag_loss),
+            (BicycleStat::HazardLoss, energy_budget.hazard_loss),
+        ] {
+            bicycle_stats.enqueue_value_for_stat(&stat, value);
+        }
     }
 
     pub fn bottom_panel_ui(
         mut ui_state: ResMut<UiState>,
         mut contexts: EguiContexts,
         bicycle_stats: Res<BicycleStats>,
+        energy_budget: Res<EnergyBudget>,
     ) {
         egui::TopBottomPanel::new(TopBottomSide::Bottom, "Bottom Panel").show(
             contexts.ctx_mut(),
@@ -183,7 +468,7 @@ impl UIPlugin {
                         ui.heading("Bicycle Statistics");
 
                         ui.label(format!(
-                            "Frame Grade: {:.1}",
+                            "Grade: {:.1}",
                             bicycle_stats.get_avg(&BicycleStat::Grade)
                         ));
 
@@ -240,6 +525,29 @@ impl UIPlugin {
                             );
                         });
                     });
+
+                    ui.separator();
+
+                    ui.vertical(|ui| {
+                        ui.heading("Power Meter");
+                        ui.label(format!(
+                            "Crank Power: {:.0} W",
+                            bicycle_stats.get_avg(&BicycleStat::CrankPower)
+                        ));
+                        ui.label(format!("Crank Work: {:.0} J", energy_budget.crank_work));
+                    });
+
+                    ui.separator();
+
+                    ui.vertical(|ui| {
+                        ui.heading("Energy Budget");
+                        ui.label(format!("Kinetic: {:.0} J", energy_budget.kinetic));
+                        ui.label(format!("Potential: {:.0} J", energy_budget.potential));
+                        ui.label(format!("Rolling Loss: {:.0} J", energy_budget.rolling_loss));
+                        ui.label(format!("Drag Loss: {:.0} J", energy_budget.drag_loss));
+                        ui.label(format!("Hazard Loss: {:.0} J", energy_budget.hazard_loss));
+                        ui.label(format!("Unaccounted: {:.0} J", energy_budget.unaccounted()));
+                    });
                 });
             },
         );
@@ -0,0 +1,282 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use strum::IntoEnumIterator;
+
+use crate::{
+    course::resources::Course,
+    track::{
+        events::TrackEvent,
+        format::{Track, TrackFeature, MIN_FEATURE_SIZE, PLATFORM_PERIODS},
+        resources::{ActiveTrack, EditorTool, TrackEditor},
+    },
+    world::surfaces::SurfaceType,
+};
+
+use super::plugin::UIPlugin;
+
+// A value still being dragged or typed, which isn't applied to the track yet
+fn is_settling(response: &egui::Response) -> bool {
+    response.dragged() || response.has_focus()
+}
+
+impl UIPlugin {
+    pub fn track_editor_window_ui(
+        mut contexts: EguiContexts,
+        mut editor: ResMut<TrackEditor>,
+        mut active_track: ResMut<ActiveTrack>,
+        mut track_events: EventWriter<TrackEvent>,
+    ) {
+        let editor = editor.as_mut();
+        let mut open = editor.enabled;
+
+        // Edit a copy so the track (and terrain) only change when a value does,
+        // and only once it's let go. A control point dragged in the world is
+        // applied by the pointer when it's let go.
+        let mut edited = editor
+            .draft
+            .take()
+            .or_else(|| active_track.track.as_deref().cloned());
+        let mut settling = editor.dragging;
+
+        egui::Window::new("Track Editor")
+            .open(&mut open)
+            .default_width(320.0)
+            .show(contexts.ctx_mut(), |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("File:");
+                    ui.text_edit_singleline(&mut editor.path);
+                });
+
+                ui.horizontal(|ui| {
+                    if ui.button("New").clicked() {
+                        track_events.send(TrackEvent::New);
+                    }
+                    if ui.button("Load").clicked() {
+                        track_events.send(TrackEvent::Load(editor.path.trim().into()));
+                    }
+                    if ui
+                        .add_enabled(edited.is_some(), egui::Button::new("Save"))
+                        .clicked()
+                    {
+                        track_events.send(TrackEvent::Save(editor.path.trim().into()));
+                    }
+                    if ui
+                        .add_enabled(edited.is_some(), egui::Button::new("Close"))
+                        .clicked()
+                    {
+                        track_events.send(TrackEvent::Close);
+                    }
+                });
+
+                if let Some(error) = &active_track.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+
+                let Some(track) = edited.as_mut() else {
+                    ui.label("Start a new track or load one to edit.");
+                    return;
+                };
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Name:");
+                    settling |= is_settling(&ui.text_edit_singleline(&mut track.name));
+                });
+
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Tool:");
+                    for tool in EditorTool::iter() {
+                        ui.selectable_value(&mut editor.tool, tool, format!("{:?}", tool));
+                    }
+                });
+                ui.label("Left click places or drags, right click removes a control point.");
+                ui.label("Press C for a free camera to move around the track.");
+
+                ui.separator();
+                settling |= Self::control_point_ui(ui, track, editor.selected);
+
+                ui.separator();
+                settling |= Self::features_ui(ui, track);
+
+                ui.separator();
+                settling |= Self::course_ui(ui, track);
+            });
+
+        editor.enabled = open;
+
+        if let Some(edited) = edited {
+            if settling {
+                editor.draft = Some(edited);
+            } else if active_track.track.as_deref() != Some(&edited) {
+                active_track.track = Some(Arc::new(edited));
+            }
+        }
+    }
+
+    // Each of these returns whether a value is still being edited
+    fn control_point_ui(ui: &mut egui::Ui, track: &mut Track, selected: Option<usize>) -> bool {
+        let Some(index) = selected.filter(|&index| index < track.control_points.len()) else {
+            ui.label("No control point selected.");
+            return false;
+        };
+        let mut settling = false;
+        let x_range = track.control_point_x_range(index);
+        let point = &mut track.control_points[index];
+
+        ui.heading("Control Point");
+        ui.horizontal(|ui| {
+            settling |= is_settling(
+                &ui.add(
+                    egui::DragValue::new(&mut point.x)
+                        .range(x_range)
+                        .prefix("x: "),
+                ),
+            );
+            settling |=
+                is_settling(&ui.add(egui::DragValue::new(&mut point.height).prefix("height: ")));
+        });
+
+        egui::ComboBox::from_label("Surface to next point")
+            .selected_text(format!("{:?}", point.surface))
+            .show_ui(ui, |ui| {
+                for surface in SurfaceType::iter() {
+                    ui.selectable_value(&mut point.surface, surface, format!("{:?}", surface));
+                }
+            });
+
+        settling
+    }
+
+    fn course_ui(ui: &mut egui::Ui, track: &mut Track) -> bool {
+        ui.heading("Course");
+        let mut settling = false;
+
+        // Spans the track's control points to begin with
+        let first = track.control_points.first().map(|point| point.x);
+        let last = track.control_points.last().map(|point| point.x);
+        let span = match (first, last) {
+            (Some(start), Some(finish)) if finish > start => Some(Course { start, finish }),
+            _ => None,
+        };
+
+        let mut raced = track.course.is_some();
+        ui.add_enabled(
+            raced || span.is_some(),
+            egui::Checkbox::new(&mut raced, "Start gate and finish line"),
+        );
+        if !raced && span.is_none() {
+            ui.label("Place at least two control points to add a course.");
+        }
+
+        match (raced, track.course.as_mut()) {
+            (true, Some(course)) => {
+                let finish = course.finish;
+                ui.horizontal(|ui| {
+                    settling |= is_settling(
+                        &ui.add(
+                            egui::DragValue::new(&mut course.start)
+                                .range(f64::MIN..=finish - 1.0)
+                                .prefix("start: "),
+                        ),
+                    );
+                    settling |= is_settling(
+                        &ui.add(
+                            egui::DragValue::new(&mut course.finish)
+                                .range(course.start + 1.0..=f64::MAX)
+                                .prefix("finish: "),
+                        ),
+                    );
+                });
+            }
+            (true, None) => track.course = span,
+            (false, _) => track.course = None,
+        }
+
+        settling
+    }
+
+    fn features_ui(ui: &mut egui::Ui, track: &mut Track) -> bool {
+        ui.heading("Features");
+
+        let mut removed = None;
+        let mut settling = false;
+
+        for (index, feature) in track.features.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                let mut size = |ui: &mut egui::Ui, value: &mut f64, prefix: &str| {
+                    settling |= is_settling(
+                        &ui.add(
+                            egui::DragValue::new(value)
+                                .range(MIN_FEATURE_SIZE..=f64::MAX)
+                                .prefix(prefix),
+                        ),
+                    );
+                };
+
+                match feature {
+                    TrackFeature::Ramp { length, height, .. } => {
+                        ui.label("Ramp");
+                        size(ui, length, "length: ");
+                        size(ui, height, "height: ");
+                    }
+                    TrackFeature::Gap { width, .. } => {
+                        ui.label("Gap");
+                        size(ui, width, "width: ");
+                    }
+                    TrackFeature::Obstacle { width, height, .. } => {
+                        ui.label("Obstacle");
+                        size(ui, width, "width: ");
+                        size(ui, height, "height: ");
+                    }
+                    TrackFeature::Checkpoint { .. } => {
+                        ui.label("Checkpoint");
+                    }
+                    TrackFeature::Seesaw { length, .. } => {
+                        ui.label("Seesaw");
+                        size(ui, length, "length: ");
+                    }
+                    TrackFeature::Bridge { span, .. } => {
+                        ui.label("Bridge");
+                        size(ui, span, "span: ");
+                    }
+                    TrackFeature::Platform {
+                        width,
+                        travel_x,
+                        travel_y,
+                        period,
+                        ..
+                    } => {
+                        ui.label("Platform");
+                        size(ui, width, "width: ");
+                        for (value, prefix) in [(travel_x, "travel x: "), (travel_y, "travel y: ")]
+                        {
+                            settling |=
+                                is_settling(&ui.add(egui::DragValue::new(value).prefix(prefix)));
+                        }
+                        settling |= is_settling(
+                            &ui.add(
+                                egui::DragValue::new(period)
+                                    .range(PLATFORM_PERIODS)
+                                    .prefix("period: "),
+                            ),
+                        );
+                    }
+                }
+                settling |=
+                    is_settling(&ui.add(egui::DragValue::new(feature.x_mut()).prefix("x: ")));
+
+                if ui.small_button("Remove").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+
+        if let Some(index) = removed {
+            track.features.remove(index);
+        }
+
+        settling
+    }
+}
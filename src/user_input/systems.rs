@@ -8,6 +8,7 @@ use crate::{
         CameraPanDirection, CameraPanEvent, CameraZoomDirection, CameraZoomEvent,
         CycleCameraModeEvent,
     },
+    telemetry::events::ToggleTelemetryRecordingEvent,
 };
 
 use super::plugin::UserInputPlugin;
@@ -57,6 +58,9 @@ impl UserInputPlugin {
                 KeyCode::Enter => {
                     commands.run_system(systems.0["spawn_bicycle"]);
                 }
+                KeyCode::KeyT => {
+                    commands.send_event(ToggleTelemetryRecordingEvent);
+                }
                 KeyCode::KeyQ => {}
                 _ => {}
             }
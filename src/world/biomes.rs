@@ -0,0 +1,178 @@
+use std::sync::Arc;
+
+use bevy::utils::HashMap;
+use noise::{NoiseFn, Perlin};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use super::{
+    features::FeatureType,
+    generators::TerrainGenerator,
+    plugin::WorldTerrainPlugin,
+    resources::{TerrainProfile, TerrainSettings},
+    surfaces::{patch_roll, SurfaceType},
+};
+
+// Biome noise is sampled per chunk, so this sets roughly how many chunks a biome spans
+const BIOME_FREQUENCY: f64 = 0.05;
+// Offsets the biome noise seed so it doesn't mirror the height noise
+const BIOME_SEED_OFFSET: u32 = 0x5EED_B10E;
+
+// Biomes in order of increasing biome noise. The thresholds split the noise
+// distribution into roughly equal shares, and values within BIOME_BLEND of a
+// threshold blend the neighbouring biomes.
+const BIOME_ORDER: [Biome; 5] = [
+    Biome::Desert,
+    Biome::Meadow,
+    Biome::UrbanRoad,
+    Biome::Mountain,
+    Biome::Snow,
+];
+const BIOME_THRESHOLDS: [f64; 4] = [-0.29, -0.13, 0.13, 0.29];
+const BIOME_BLEND: f64 = 0.04;
+
+#[derive(Debug, EnumIter, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Biome {
+    Meadow,
+    Desert,
+    Mountain,
+    Snow,
+    UrbanRoad,
+}
+
+impl Biome {
+    pub fn terrain_settings(&self, base: &TerrainSettings) -> TerrainSettings {
+        let (profile, octaves, frequency_scale, amplitude_scale) = match self {
+            Biome::Meadow => (TerrainProfile::FbmPerlin, base.octaves, 1.0, 1.0),
+            Biome::Desert => (TerrainProfile::Simplex, 2, 3.0, 0.6),
+            Biome::Mountain => (
+                TerrainProfile::RidgedMultifractal,
+                base.octaves + 2,
+                1.0,
+                4.0,
+            ),
+            Biome::Snow => (TerrainProfile::RidgedMultifractal, base.octaves, 1.5, 3.0),
+            Biome::UrbanRoad => (TerrainProfile::FbmPerlin, 1, 0.5, 0.2),
+        };
+
+        TerrainSettings {
+            profile,
+            octaves,
+            frequency: base.frequency * frequency_scale,
+            amplitude: base.amplitude * amplitude_scale,
+            biomes: false,
+            features: false,
+            ..base.clone()
+        }
+    }
+
+    // Surfaces found in this biome and how common each one is
+    pub fn surfaces(&self) -> &'static [(SurfaceType, f64)] {
+        match self {
+            Biome::Meadow => &[
+                (SurfaceType::Dirt, 0.7),
+                (SurfaceType::Mud, 0.15),
+                (SurfaceType::Gravel, 0.15),
+            ],
+            Biome::Desert => &[(SurfaceType::Sand, 0.8), (SurfaceType::Gravel, 0.2)],
+            Biome::Mountain => &[(SurfaceType::Gravel, 0.6), (SurfaceType::Dirt, 0.4)],
+            Biome::Snow => &[(SurfaceType::Ice, 0.5), (SurfaceType::Gravel, 0.5)],
+            Biome::UrbanRoad => &[(SurfaceType::Asphalt, 0.9), (SurfaceType::Gravel, 0.1)],
+        }
+    }
+
+    // Features found in this biome and how common each one is
+    pub fn features(&self) -> &'static [(FeatureType, f64)] {
+        match self {
+            Biome::Meadow => &[
+                (FeatureType::Log, 0.4),
+                (FeatureType::Kicker, 0.3),
+                (FeatureType::Rock, 0.3),
+            ],
+            Biome::Desert => &[(FeatureType::Rock, 0.7), (FeatureType::Drop, 0.3)],
+            Biome::Mountain => &[
+                (FeatureType::Rock, 0.5),
+                (FeatureType::Drop, 0.3),
+                (FeatureType::Log, 0.2),
+            ],
+            Biome::Snow => &[(FeatureType::Rock, 0.5), (FeatureType::Log, 0.5)],
+            Biome::UrbanRoad => &[
+                (FeatureType::Stairs, 0.5),
+                (FeatureType::Kicker, 0.3),
+                (FeatureType::Drop, 0.2),
+            ],
+        }
+    }
+}
+
+fn smoothstep(t: f64) -> f64 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+pub struct BiomeTerrainGenerator {
+    seed: u32,
+    biome_noise: Perlin,
+    generators: HashMap<Biome, Arc<dyn TerrainGenerator>>,
+}
+
+impl BiomeTerrainGenerator {
+    pub fn new(seed: u32, base: &TerrainSettings) -> Self {
+        BiomeTerrainGenerator {
+            seed,
+            biome_noise: Perlin::new(seed.wrapping_add(BIOME_SEED_OFFSET)),
+            generators: Biome::iter()
+                .map(|biome| (biome, biome.terrain_settings(base).build_generator(seed)))
+                .collect(),
+        }
+    }
+
+    // The (at most two) biomes present at x, with weights summing to one.
+    pub fn biome_weights(&self, x: f64) -> [(Biome, f64); 2] {
+        let chunk_position = x / WorldTerrainPlugin::CHUNK_WIDTH as f64;
+        let noise = self.biome_noise.get([chunk_position * BIOME_FREQUENCY]);
+
+        let nearest = BIOME_THRESHOLDS
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| (noise - **a).abs().total_cmp(&(noise - **b).abs()))
+            .map(|(i, _)| i)
+            .unwrap();
+
+        let blend = smoothstep((noise - BIOME_THRESHOLDS[nearest]) / (2.0 * BIOME_BLEND) + 0.5);
+
+        [
+            (BIOME_ORDER[nearest], 1.0 - blend),
+            (BIOME_ORDER[nearest + 1], blend),
+        ]
+    }
+}
+
+impl TerrainGenerator for BiomeTerrainGenerator {
+    fn height(&self, x: f64) -> f64 {
+        self.biome_weights(x)
+            .iter()
+            .filter(|(_, weight)| *weight > 0.0)
+            .map(|(biome, weight)| weight * self.generators[biome].height(x))
+            .sum()
+    }
+
+    fn surface(&self, x: f64) -> SurfaceType {
+        // Near a boundary, each biome supplies surfaces in proportion to its weight
+        let [(a, weight_a), (b, _)] = self.biome_weights(x);
+        let biome = if patch_roll(self.seed, x, 1) < weight_a {
+            a
+        } else {
+            b
+        };
+
+        SurfaceType::pick(biome.surfaces(), patch_roll(self.seed, x, 2))
+    }
+
+    fn biome(&self, x: f64) -> Option<Biome> {
+        self.biome_weights(x)
+            .into_iter()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(biome, _)| biome)
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use avian2d::prelude::Collider;
+use bevy::{prelude::*, tasks::Task, utils::HashMap};
+
+use super::{
+    components::{TerrainChunkSamples, TerrainDetail},
+    contraptions::ContraptionLayout,
+    features::PlacedFeature,
+    hazards::LiquidHazard,
+    surfaces::SurfaceType,
+};
+
+// Enough to hold every chunk the UI's largest MaxTerrainChunkCount keeps
+// alive, twice over, so riding back and forth never regenerates
+pub const TERRAIN_CHUNK_CACHE_CAPACITY: usize = 256;
+
+// (TerrainSeed, chunk index, detail)
+pub type TerrainChunkKey = (u32, i128, TerrainDetail);
+
+// Everything needed to spawn a chunk, built off the main thread. Its meshes
+// are added as assets once it's cached, so every spawn of the chunk shares
+// them. Contraptions build theirs as they're spawned. Far chunks have no colliders, features, contraptions or hazards.
+pub struct TerrainChunkData<M = Mesh> {
+    pub samples: TerrainChunkSamples,
+    pub mesh: M,
+    pub surface_colliders: Vec<(SurfaceType, Collider, Transform)>,
+    pub features: Vec<(PlacedFeature, Transform, Collider, M)>,
+    pub contraptions: Vec<ContraptionLayout>,
+    pub hazards: Vec<(LiquidHazard, M)>,
+}
+
+pub type CachedTerrainChunk = TerrainChunkData<Handle<Mesh>>;
+
+impl TerrainChunkData {
+    pub fn add_meshes(self, meshes: &mut Assets<Mesh>) -> CachedTerrainChunk {
+        TerrainChunkData {
+            samples: self.samples,
+            mesh: meshes.add(self.mesh),
+            surface_colliders: self.surface_colliders,
+            features: self
+                .features
+                .into_iter()
+                .map(|(feature, transform, collider, mesh)| {
+                    (feature, transform, collider, meshes.add(mesh))
+                })
+                .collect(),
+            contraptions: self.contraptions,
+            hazards: self
+                .hazards
+                .into_iter()
+                .map(|(hazard, mesh)| (hazard, meshes.add(mesh)))
+                .collect(),
+        }
+    }
+}
+
+// Least recently used cache of generated chunks. It only holds chunks from the
+// current generator, so it's cleared whenever the generator is rebuilt. An
+// evicted chunk's meshes are freed once no spawned chunk uses them.
+#[derive(Resource)]
+pub struct TerrainChunkCache {
+    capacity: usize,
+    // Each entry remembers the tick it was last used on
+    entries: HashMap<TerrainChunkKey, (Arc<CachedTerrainChunk>, u64)>,
+    tick: u64,
+}
+
+impl Default for TerrainChunkCache {
+    fn default() -> Self {
+        TerrainChunkCache::new(TERRAIN_CHUNK_CACHE_CAPACITY)
+    }
+}
+
+impl TerrainChunkCache {
+    pub fn new(capacity: usize) -> Self {
+        TerrainChunkCache {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            tick: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&mut self, key: &TerrainChunkKey) -> Option<Arc<CachedTerrainChunk>> {
+        self.tick += 1;
+        let tick = self.tick;
+
+        self.entries.get_mut(key).map(|(data, last_used)| {
+            *last_used = tick;
+            data.clone()
+        })
+    }
+
+    pub fn insert(
+        &mut self,
+        key: TerrainChunkKey,
+        data: CachedTerrainChunk,
+    ) -> Arc<CachedTerrainChunk> {
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let least_recent = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| *key);
+
+            if let Some(least_recent) = least_recent {
+                self.entries.remove(&least_recent);
+            }
+        }
+
+        self.tick += 1;
+        let data = Arc::new(data);
+        self.entries.insert(key, (data.clone(), self.tick));
+        data
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+// Chunks being generated on the AsyncComputeTaskPool
+#[derive(Resource, Default)]
+pub struct PendingTerrainChunks(pub HashMap<TerrainChunkKey, Task<TerrainChunkData>>);
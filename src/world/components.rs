@@ -1,7 +1,48 @@
 use bevy::prelude::*;
 
+use super::{resources::MaxTerrainChunkCount, surfaces::SurfaceType};
+
 #[derive(Component)]
 pub struct Terrain;
 
 #[derive(Component, PartialEq)]
 pub struct TerrainChunk(pub i128);
+
+// Terrain chunks are streamed in around every entity carrying this, and kept
+// while any anchor is in range
+#[derive(Component, Default)]
+pub struct TerrainStreamingAnchor {
+    // Chunks kept either side of the anchor's own. None uses half of
+    // MaxTerrainChunkCount.
+    pub radius: Option<u8>,
+}
+
+impl TerrainStreamingAnchor {
+    pub fn chunk_radius(&self, terrain_chunk_count: &MaxTerrainChunkCount) -> i128 {
+        self.radius.unwrap_or(terrain_chunk_count.0 / 2) as i128
+    }
+}
+
+// How finely a chunk is sampled. Chunks around the bicycle get dense samples
+// and colliders, the rest are coarse and only drawn.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TerrainDetail {
+    Near,
+    Far,
+}
+
+// World-space height samples of a chunk and the surface of each segment
+// between them, None where the segment is a gap
+#[derive(Component, Clone)]
+pub struct TerrainChunkSamples {
+    pub heights: Vec<f64>,
+    pub surfaces: Vec<Option<SurfaceType>>,
+}
+
+// Rock, log, jump or other feature spawned with a chunk
+#[derive(Component)]
+pub struct TerrainFeature;
+
+// Marks a terrain collider with the surface it is made of
+#[derive(Component, Clone, Copy)]
+pub struct TerrainSurface(pub SurfaceType);
@@ -0,0 +1,317 @@
+use std::f64::consts::TAU;
+
+use avian2d::{math::Vector, prelude::*};
+use bevy::{
+    color::palettes::css::{DARK_SLATE_GRAY, SIENNA, SLATE_GRAY, TAN},
+    math::DVec2,
+    prelude::*,
+};
+
+use crate::GameLayer;
+
+use super::{
+    generators::TerrainGenerator, plugin::WorldTerrainPlugin, resources::PropMaterials,
+    surfaces::SurfaceType, terrain_query::TerrainSample,
+};
+
+// Wooden parts
+const PLANK_THICKNESS: f64 = 6.0;
+const SEESAW_PIVOT_HEIGHT: f64 = 24.0;
+// How far a seesaw tips either way before it's stopped
+const SEESAW_TILT_LIMIT: f64 = 0.4;
+// Bridge decks are this much longer than their span, so they sag
+const BRIDGE_SLACK: f64 = 1.04;
+const BRIDGE_POST_HEIGHT: f64 = 40.0;
+const PLATFORM_THICKNESS: f64 = 12.0;
+// Same as the chunk's other features
+const CONTRAPTION_Z: f32 = 1.0;
+
+// Course elements made of moving bodies, laid out from their left edge
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContraptionKind {
+    // Plank balanced on a pivot halfway along it
+    Seesaw {
+        length: f64,
+    },
+    // Deck of planks jointed end to end, hung between two posts
+    Bridge {
+        span: f64,
+        planks: u32,
+    },
+    // Deck sliding out by `travel` and back again every `period` seconds
+    Platform {
+        width: f64,
+        travel: DVec2,
+        period: f64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlacedContraption {
+    pub x: f64,
+    pub kind: ContraptionKind,
+}
+
+impl ContraptionKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ContraptionKind::Seesaw { .. } => "Seesaw",
+            ContraptionKind::Bridge { .. } => "Bridge",
+            ContraptionKind::Platform { .. } => "Platform",
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        match self {
+            ContraptionKind::Seesaw { length } => *length,
+            ContraptionKind::Bridge { span, .. } => *span,
+            ContraptionKind::Platform { width, .. } => *width,
+        }
+    }
+}
+
+// Moves a kinematic body back and forth from `origin` by `travel`, easing
+// in and out at either end
+#[derive(Component, Debug, Clone, Copy)]
+pub struct KinematicPath {
+    pub origin: Vector,
+    pub travel: Vector,
+    pub period: f64,
+    // Seconds since the body spawned at `origin`
+    pub elapsed: f64,
+}
+
+impl KinematicPath {
+    pub fn new(origin: Vector, travel: Vector, period: f64) -> Self {
+        KinematicPath {
+            origin,
+            travel,
+            period,
+            elapsed: 0.0,
+        }
+    }
+
+    // Where the body is `time` seconds after spawning
+    pub fn position_at(&self, time: f64) -> Vector {
+        let phase = (1.0 - (TAU * time / self.period.max(f64::EPSILON)).cos()) / 2.0;
+        self.origin + self.travel * phase
+    }
+}
+
+// A contraption with the world-space ground points under its ends
+#[derive(Debug, Clone, Copy)]
+pub struct ContraptionLayout {
+    pub kind: ContraptionKind,
+    pub start: DVec2,
+    pub end: DVec2,
+}
+
+impl ContraptionLayout {
+    pub fn new(contraption: &PlacedContraption, generator: &dyn TerrainGenerator) -> Self {
+        let ground = |x: f64| DVec2::new(x, TerrainSample::at(generator, x).height);
+
+        ContraptionLayout {
+            kind: contraption.kind,
+            start: ground(contraption.x),
+            end: ground(contraption.x + contraption.kind.width()),
+        }
+    }
+
+    // Spawns the contraption's bodies and joints as children of a chunk
+    pub fn spawn(
+        &self,
+        chunk: &mut ChildBuilder,
+        chunk_index: i128,
+        meshes: &mut Assets<Mesh>,
+        prop_materials: &mut PropMaterials,
+        materials: &mut Assets<StandardMaterial>,
+    ) {
+        let chunk_origin = DVec2::new(
+            (chunk_index as f32).round() as f64 * WorldTerrainPlugin::CHUNK_WIDTH as f64,
+            0.0,
+        );
+        let (start, end) = (self.start - chunk_origin, self.end - chunk_origin);
+        let at = |point: DVec2, angle: f64| {
+            Transform::from_xyz(point.x as f32, point.y as f32, CONTRAPTION_Z)
+                .with_rotation(Quat::from_rotation_z(angle as f32))
+        };
+        let mut material =
+            |color: Srgba| MeshMaterial3d(prop_materials.get(materials, color.into(), false));
+        let wood = SurfaceType::Dirt.physics_friction();
+
+        match self.kind {
+            ContraptionKind::Seesaw { length } => {
+                let pivot = (start + end) / 2.0;
+
+                let fulcrum = chunk
+                    .spawn((
+                        Name::new("Seesaw Pivot"),
+                        RigidBody::Static,
+                        Mesh3d(meshes.add(Triangle2d::new(
+                            Vec2::new(-SEESAW_PIVOT_HEIGHT as f32, -SEESAW_PIVOT_HEIGHT as f32),
+                            Vec2::new(SEESAW_PIVOT_HEIGHT as f32, -SEESAW_PIVOT_HEIGHT as f32),
+                            Vec2::new(0.0, SEESAW_PIVOT_HEIGHT as f32),
+                        ))),
+                        material(DARK_SLATE_GRAY),
+                        at(pivot, 0.0),
+                    ))
+                    .id();
+
+                let plank = chunk
+                    .spawn((
+                        Name::new("Seesaw Plank"),
+                        RigidBody::Dynamic,
+                        Collider::rectangle(length, PLANK_THICKNESS),
+                        CollisionLayers::new(
+                            [GameLayer::Props],
+                            [
+                                GameLayer::World,
+                                GameLayer::Wheels,
+                                GameLayer::Frame,
+                                GameLayer::Props,
+                            ],
+                        ),
+                        wood,
+                        Restitution::new(0.0),
+                        Mesh3d(meshes.add(Rectangle::new(length as f32, PLANK_THICKNESS as f32))),
+                        material(SIENNA),
+                        at(
+                            pivot + DVec2::new(0.0, SEESAW_PIVOT_HEIGHT + PLANK_THICKNESS / 2.0),
+                            0.0,
+                        ),
+                    ))
+                    .id();
+
+                // The pivot has no collider, so the plank only rests on the joint
+                chunk.spawn((
+                    Name::new("Seesaw Joint"),
+                    RevoluteJoint::new(fulcrum, plank)
+                        .with_local_anchor_1(Vector::new(0.0, SEESAW_PIVOT_HEIGHT))
+                        .with_local_anchor_2(Vector::new(0.0, -PLANK_THICKNESS / 2.0))
+                        .with_angle_limits(-SEESAW_TILT_LIMIT, SEESAW_TILT_LIMIT),
+                ));
+            }
+            ContraptionKind::Bridge { planks, .. } => {
+                let planks = planks.max(1);
+                let angle = (end - start).to_angle();
+                let plank_length = (end - start).length() * BRIDGE_SLACK / planks as f64;
+
+                let post_mesh = meshes.add(
+                    Mesh::from(Rectangle::new(
+                        PLANK_THICKNESS as f32,
+                        BRIDGE_POST_HEIGHT as f32,
+                    ))
+                    .translated_by(Vec3::new(
+                        0.0,
+                        BRIDGE_POST_HEIGHT as f32 / 2.0,
+                        0.0,
+                    )),
+                );
+                let post_material = material(SIENNA);
+                let mut post = |name: &str, point: DVec2| {
+                    chunk
+                        .spawn((
+                            Name::new(format!("Bridge {} Post", name)),
+                            RigidBody::Static,
+                            Mesh3d(post_mesh.clone()),
+                            post_material.clone(),
+                            at(point, 0.0),
+                        ))
+                        .id()
+                };
+                let left_post = post("Left", start);
+                let right_post = post("Right", end);
+
+                let plank_mesh =
+                    meshes.add(Rectangle::new(plank_length as f32, PLANK_THICKNESS as f32));
+                let plank_material = material(TAN);
+
+                let mut previous = (left_post, Vector::ZERO);
+                for i in 0..planks {
+                    let center = start + (end - start) * ((i as f64 + 0.5) / planks as f64);
+
+                    // Planks collide with everything loose on the course,
+                    // but not with the neighbours they're jointed to
+                    let plank = chunk
+                        .spawn((
+                            Name::new(format!("Bridge Plank({})", i)),
+                            RigidBody::Dynamic,
+                            Collider::rectangle(plank_length, PLANK_THICKNESS),
+                            CollisionLayers::new(
+                                [GameLayer::Props],
+                                [GameLayer::Wheels, GameLayer::Frame, GameLayer::Props],
+                            ),
+                            wood,
+                            Restitution::new(0.0),
+                            Mesh3d(plank_mesh.clone()),
+                            plank_material.clone(),
+                            at(center, angle),
+                        ))
+                        .id();
+
+                    chunk.spawn((
+                        Name::new(format!("Bridge Joint({})", i)),
+                        RevoluteJoint::new(previous.0, plank)
+                            .with_local_anchor_1(previous.1)
+                            .with_local_anchor_2(Vector::new(-plank_length / 2.0, 0.0)),
+                        JointCollisionDisabled,
+                    ));
+                    previous = (plank, Vector::new(plank_length / 2.0, 0.0));
+                }
+
+                chunk.spawn((
+                    Name::new(format!("Bridge Joint({})", planks)),
+                    RevoluteJoint::new(previous.0, right_post)
+                        .with_local_anchor_1(previous.1)
+                        .with_local_anchor_2(Vector::ZERO),
+                ));
+            }
+            ContraptionKind::Platform {
+                width,
+                travel,
+                period,
+            } => {
+                // Starts with its top level with the ground at its left edge
+                let center = start + DVec2::new(width / 2.0, -PLATFORM_THICKNESS / 2.0);
+
+                chunk.spawn((
+                    Name::new("Platform"),
+                    RigidBody::Kinematic,
+                    KinematicPath::new(center + chunk_origin, travel, period),
+                    // Set up front, so the first step doesn't move it from the origin
+                    Position(center + chunk_origin),
+                    Collider::rectangle(width, PLATFORM_THICKNESS),
+                    CollisionLayers::new(
+                        [GameLayer::Props],
+                        [GameLayer::Wheels, GameLayer::Frame, GameLayer::Props],
+                    ),
+                    SurfaceType::Asphalt.physics_friction(),
+                    Restitution::new(0.0),
+                    Mesh3d(meshes.add(Rectangle::new(width as f32, PLATFORM_THICKNESS as f32))),
+                    material(SLATE_GRAY),
+                    at(center, 0.0),
+                ));
+            }
+        }
+    }
+}
+
+impl WorldTerrainPlugin {
+    // Kinematic bodies are moved by velocity, so whatever rides on them is
+    // carried along by friction. Each path starts from its spawn, so a body
+    // never has to jump to catch up with the clock.
+    pub fn follow_kinematic_paths(
+        mut bodies: Query<(&mut KinematicPath, &Position, &mut LinearVelocity)>,
+        time: Res<Time>,
+    ) {
+        let dt = time.delta_secs_f64();
+        if dt <= 0.0 {
+            return;
+        }
+
+        for (mut path, position, mut lin_vel) in bodies.iter_mut() {
+            path.elapsed += dt;
+            lin_vel.0 = (path.position_at(path.elapsed) - position.0) / dt;
+        }
+    }
+}
@@ -0,0 +1,509 @@
+use std::{error::Error, fmt, fs, io, path::Path};
+
+use super::{generators::TerrainGenerator, surfaces::SurfaceType};
+
+// Gravity is 100 units/s², so a meter is roughly ten world units
+pub const UNITS_PER_METER: f64 = 10.0;
+
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+#[derive(Debug)]
+pub enum ElevationProfileError {
+    Io(io::Error),
+    UnsupportedFormat(String),
+    Parse(String),
+    // A row whose distance is before the row above's
+    OutOfOrder {
+        row: String,
+        distance: f64,
+        previous: f64,
+    },
+    TooFewPoints,
+}
+
+impl fmt::Display for ElevationProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ElevationProfileError::Io(err) => write!(f, "{}", err),
+            ElevationProfileError::UnsupportedFormat(extension) => {
+                write!(
+                    f,
+                    "unsupported file type {:?}, expected gpx or csv",
+                    extension
+                )
+            }
+            ElevationProfileError::Parse(message) => write!(f, "{}", message),
+            ElevationProfileError::OutOfOrder {
+                row,
+                distance,
+                previous,
+            } => write!(
+                f,
+                "{}: distance {} comes before the previous point's {}",
+                row, distance, previous
+            ),
+            ElevationProfileError::TooFewPoints => {
+                write!(f, "a profile needs at least two points")
+            }
+        }
+    }
+}
+
+impl Error for ElevationProfileError {}
+
+impl From<io::Error> for ElevationProfileError {
+    fn from(err: io::Error) -> Self {
+        ElevationProfileError::Io(err)
+    }
+}
+
+// Elevation along a real-world track. Distances start at zero and elevations
+// are relative to the lowest point, both in meters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElevationProfile {
+    pub name: String,
+    distances: Vec<f64>,
+    elevations: Vec<f64>,
+}
+
+impl ElevationProfile {
+    pub fn load(path: &Path) -> Result<Self, ElevationProfileError> {
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let text = fs::read_to_string(path)?;
+
+        match extension.as_str() {
+            "gpx" => Self::from_gpx(name, &text),
+            "csv" => Self::from_csv(name, &text),
+            _ => Err(ElevationProfileError::UnsupportedFormat(extension)),
+        }
+    }
+
+    // Rows of `distance,elevation` in meters. Blank lines, `#` comments and a
+    // header row are skipped.
+    pub fn from_csv(name: String, text: &str) -> Result<Self, ElevationProfileError> {
+        let mut points = vec![];
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut columns = line.split(',').map(str::trim);
+            let parsed = match (columns.next(), columns.next()) {
+                (Some(distance), Some(elevation)) => distance
+                    .parse::<f64>()
+                    .ok()
+                    .zip(elevation.parse::<f64>().ok()),
+                _ => None,
+            };
+
+            match parsed {
+                Some((distance, elevation)) => {
+                    points.push((format!("line {}", line_index + 1), distance, elevation))
+                }
+                None if points.is_empty() => continue, // Header
+                None => {
+                    return Err(ElevationProfileError::Parse(format!(
+                        "line {}: expected distance,elevation but found {:?}",
+                        line_index + 1,
+                        line
+                    )))
+                }
+            }
+        }
+
+        Self::from_points(name, points)
+    }
+
+    // Track points (or route points, if there is no track) with an `<ele>`
+    // element. Distance is measured along the great circle between every
+    // point, including those without an elevation.
+    pub fn from_gpx(name: String, text: &str) -> Result<Self, ElevationProfileError> {
+        let mut coordinates = Self::gpx_points(text, "trkpt")?;
+        if coordinates.is_empty() {
+            coordinates = Self::gpx_points(text, "rtept")?;
+        }
+
+        let mut points = vec![];
+        let mut distance = 0.0;
+
+        for (i, (row, lat, lon, elevation)) in coordinates.iter().enumerate() {
+            if i > 0 {
+                let (_, previous_lat, previous_lon, _) = coordinates[i - 1];
+                distance += haversine_distance(previous_lat, previous_lon, *lat, *lon);
+            }
+            if let Some(elevation) = elevation {
+                points.push((row.clone(), distance, *elevation));
+            }
+        }
+
+        Self::from_points(name, points)
+    }
+
+    // (row, lat, lon, elevation), where row names the point in errors
+    fn gpx_points(
+        text: &str,
+        element: &str,
+    ) -> Result<Vec<(String, f64, f64, Option<f64>)>, ElevationProfileError> {
+        let opening = format!("<{}", element);
+        let closing = format!("</{}>", element);
+        let mut points = vec![];
+
+        for (index, point) in text.split(opening.as_str()).skip(1).enumerate() {
+            let tag_end = point.find('>').unwrap_or(point.len());
+            let tag = &point[..tag_end];
+            let body = &point[..point.find(closing.as_str()).unwrap_or(point.len())];
+
+            let (Some(lat), Some(lon)) = (xml_attribute(tag, "lat"), xml_attribute(tag, "lon"))
+            else {
+                return Err(ElevationProfileError::Parse(format!(
+                    "{} {} is missing lat or lon",
+                    element,
+                    index + 1
+                )));
+            };
+
+            let row = format!("{} {}", element, index + 1);
+            if !lat.is_finite() || !lon.is_finite() {
+                return Err(ElevationProfileError::Parse(format!(
+                    "{}: lat and lon must be finite",
+                    row
+                )));
+            }
+
+            // Points without an elevation still count towards the distance
+            let elevation = xml_element(body, "ele").and_then(|ele| ele.parse().ok());
+            points.push((row, lat, lon, elevation));
+        }
+
+        Ok(points)
+    }
+
+    // Points are (row, distance, elevation), where row names the point in errors
+    fn from_points(
+        name: String,
+        points: Vec<(String, f64, f64)>,
+    ) -> Result<Self, ElevationProfileError> {
+        let mut distances: Vec<f64> = vec![];
+        let mut elevations = vec![];
+
+        for (row, distance, elevation) in points {
+            if !distance.is_finite() || !elevation.is_finite() {
+                return Err(ElevationProfileError::Parse(format!(
+                    "{}: distance and elevation must be finite",
+                    row
+                )));
+            }
+
+            match distances.last() {
+                Some(&previous) if distance < previous => {
+                    return Err(ElevationProfileError::OutOfOrder {
+                        row,
+                        distance,
+                        previous,
+                    });
+                }
+                // A repeated GPS fix would give a zero length segment
+                Some(&previous) if distance == previous => continue,
+                _ => {}
+            }
+            distances.push(distance);
+            elevations.push(elevation);
+        }
+
+        if distances.len() < 2 {
+            return Err(ElevationProfileError::TooFewPoints);
+        }
+
+        let start = distances[0];
+        let lowest = elevations.iter().copied().fold(f64::MAX, f64::min);
+
+        Ok(ElevationProfile {
+            name,
+            distances: distances.iter().map(|distance| distance - start).collect(),
+            elevations: elevations
+                .iter()
+                .map(|elevation| elevation - lowest)
+                .collect(),
+        })
+    }
+
+    pub fn length(&self) -> f64 {
+        *self.distances.last().unwrap()
+    }
+
+    pub fn elevation_range(&self) -> f64 {
+        self.elevations.iter().copied().fold(0.0, f64::max)
+    }
+
+    pub fn elevation_gain(&self) -> f64 {
+        self.elevations
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).max(0.0))
+            .sum()
+    }
+
+    // Linearly interpolated elevation, held flat beyond either end of the track
+    pub fn elevation_at(&self, distance: f64) -> f64 {
+        let next = self.distances.partition_point(|&d| d <= distance);
+
+        if next == 0 {
+            return self.elevations[0];
+        }
+        if next == self.distances.len() {
+            return *self.elevations.last().unwrap();
+        }
+
+        let (d0, d1) = (self.distances[next - 1], self.distances[next]);
+        let (e0, e1) = (self.elevations[next - 1], self.elevations[next]);
+        e0 + (e1 - e0) * (distance - d0) / (d1 - d0)
+    }
+}
+
+impl TerrainGenerator for ElevationProfile {
+    fn height(&self, x: f64) -> f64 {
+        self.elevation_at(x / UNITS_PER_METER) * UNITS_PER_METER
+    }
+
+    fn surface(&self, _x: f64) -> SurfaceType {
+        SurfaceType::Asphalt
+    }
+}
+
+fn haversine_distance(lat_a: f64, lon_a: f64, lat_b: f64, lon_b: f64) -> f64 {
+    let (lat_a, lat_b) = (lat_a.to_radians(), lat_b.to_radians());
+    let delta_lat = lat_b - lat_a;
+    let delta_lon = (lon_b - lon_a).to_radians();
+
+    let a = (delta_lat / 2.0).sin().powi(2)
+        + lat_a.cos() * lat_b.cos() * (delta_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().asin()
+}
+
+// Value of `name="..."` (or single quoted) within an XML start tag
+fn xml_attribute(tag: &str, name: &str) -> Option<f64> {
+    let mut search = tag;
+
+    while let Some(found) = search.find(name) {
+        let preceded_by_space = search[..found]
+            .chars()
+            .last()
+            .is_some_and(char::is_whitespace);
+        let rest = search[found + name.len()..].trim_start();
+
+        if let (true, Some(rest)) = (preceded_by_space, rest.strip_prefix('=')) {
+            let rest = rest.trim_start();
+            let quote = rest.chars().next()?;
+            let value = &rest[1..];
+            return value[..value.find(quote)?].trim().parse().ok();
+        }
+
+        search = &search[found + name.len()..];
+    }
+
+    None
+}
+
+// Text content of the first `<name>...</name>` element
+fn xml_element<'a>(body: &'a str, name: &str) -> Option<&'a str> {
+    let start = body.find(&format!("<{}>", name))? + name.len() + 2;
+    let end = body[start..].find(&format!("</{}>", name))? + start;
+    Some(body[start..end].trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{haversine_distance, ElevationProfile, ElevationProfileError};
+
+    fn csv(text: &str) -> Result<ElevationProfile, ElevationProfileError> {
+        ElevationProfile::from_csv("test".to_string(), text)
+    }
+
+    fn gpx(text: &str) -> Result<ElevationProfile, ElevationProfileError> {
+        ElevationProfile::from_gpx("test".to_string(), text)
+    }
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "expected {} to be within {} of {}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+
+    #[test]
+    fn csv_profile_is_relative_to_its_start_and_lowest_point() {
+        let profile = csv("distance,elevation\n# a comment\n\n10,100\n60,110\n110,105\n").unwrap();
+
+        assert_eq!(profile.length(), 100.0);
+        assert_eq!(profile.elevation_at(0.0), 0.0);
+        assert_eq!(profile.elevation_at(25.0), 5.0);
+        assert_eq!(profile.elevation_at(50.0), 10.0);
+        assert_eq!(profile.elevation_range(), 10.0);
+        assert_eq!(profile.elevation_gain(), 10.0);
+    }
+
+    #[test]
+    fn csv_profile_is_held_flat_beyond_its_ends() {
+        let profile = csv("0,5\n100,15\n").unwrap();
+
+        assert_eq!(profile.elevation_at(-50.0), 0.0);
+        assert_eq!(profile.elevation_at(500.0), 10.0);
+    }
+
+    #[test]
+    fn csv_rejects_malformed_rows() {
+        let result = csv("0,100\n50,high\n");
+
+        match result {
+            Err(ElevationProfileError::Parse(message)) => assert!(message.contains("line 2")),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn csv_rejects_unsorted_rows() {
+        let result = csv("distance,elevation\n0,100\n100,110\n50,105\n");
+
+        match result {
+            Err(ElevationProfileError::OutOfOrder {
+                row,
+                distance,
+                previous,
+            }) => {
+                assert_eq!(row, "line 4");
+                assert_eq!(distance, 50.0);
+                assert_eq!(previous, 100.0);
+            }
+            other => panic!("expected an out of order error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn csv_skips_repeated_rows() {
+        let profile = csv("0,100\n50,110\n50,110\n100,100\n").unwrap();
+
+        assert_eq!(profile.length(), 100.0);
+        assert_eq!(profile.elevation_at(50.0), 10.0);
+    }
+
+    #[test]
+    fn csv_rejects_non_finite_values() {
+        for text in ["0,100\n50,NaN\n", "0,100\ninf,110\n"] {
+            match csv(text) {
+                Err(ElevationProfileError::Parse(message)) => assert!(message.contains("line 2")),
+                other => panic!("expected a parse error, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn profile_needs_two_points() {
+        assert!(matches!(
+            csv("distance,elevation\n0,100\n"),
+            Err(ElevationProfileError::TooFewPoints)
+        ));
+        assert!(matches!(csv(""), Err(ElevationProfileError::TooFewPoints)));
+    }
+
+    #[test]
+    fn gpx_measures_distance_along_track_points() {
+        // A thousandth of a degree of longitude at the equator is about 111 m
+        let profile = gpx(r#"<gpx><trk><trkseg>
+            <trkpt lat="0" lon="0"><ele>10</ele></trkpt>
+            <trkpt lat="0.0" lon='0.001'><ele>20</ele></trkpt>
+            <trkpt lat="0" lon="0.001"><ele>20</ele></trkpt>
+        </trkseg></trk></gpx>"#)
+        .unwrap();
+
+        assert_close(profile.length(), 111.19, 0.01);
+        assert_eq!(profile.elevation_at(profile.length()), 10.0);
+    }
+
+    #[test]
+    fn gpx_falls_back_to_route_points() {
+        let profile = gpx(r#"<gpx><rte>
+            <rtept lat="0" lon="0"><ele>10</ele></rtept>
+            <rtept lat="0.001" lon="0"><ele>0</ele></rtept>
+        </rte></gpx>"#)
+        .unwrap();
+
+        assert_close(profile.length(), 111.19, 0.01);
+        assert_eq!(profile.elevation_at(0.0), 10.0);
+    }
+
+    #[test]
+    fn gpx_skips_points_without_elevation() {
+        let profile = gpx(r#"<gpx><trk><trkseg>
+            <trkpt lat="0" lon="0"><ele>10</ele></trkpt>
+            <trkpt lat="0" lon="0.001"></trkpt>
+            <trkpt lat="0" lon="0.002"><ele>30</ele></trkpt>
+        </trkseg></trk></gpx>"#)
+        .unwrap();
+
+        assert_close(profile.length(), 222.39, 0.01);
+        assert_close(profile.elevation_at(profile.length() / 2.0), 10.0, 1e-9);
+    }
+
+    #[test]
+    fn gpx_measures_distance_through_points_without_elevation() {
+        // The middle point is off the line between the others, so skipping it
+        // would shorten the track
+        let profile = gpx(r#"<gpx><trk><trkseg>
+            <trkpt lat="0" lon="0"><ele>10</ele></trkpt>
+            <trkpt lat="0.001" lon="0.001"></trkpt>
+            <trkpt lat="0" lon="0.002"><ele>30</ele></trkpt>
+        </trkseg></trk></gpx>"#)
+        .unwrap();
+
+        let expected = haversine_distance(0.0, 0.0, 0.001, 0.001)
+            + haversine_distance(0.001, 0.001, 0.0, 0.002);
+        assert_close(profile.length(), expected, 1e-9);
+        assert!(profile.length() > haversine_distance(0.0, 0.0, 0.0, 0.002) + 50.0);
+        assert_close(profile.elevation_at(profile.length() / 2.0), 10.0, 1e-9);
+    }
+
+    #[test]
+    fn gpx_rejects_non_finite_elevations() {
+        let result = gpx(r#"<gpx><trk><trkseg>
+            <trkpt lat="0" lon="0"><ele>10</ele></trkpt>
+            <trkpt lat="0" lon="0.001"><ele>inf</ele></trkpt>
+        </trkseg></trk></gpx>"#);
+
+        match result {
+            Err(ElevationProfileError::Parse(message)) => assert!(message.contains("trkpt 2")),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn gpx_rejects_points_without_coordinates() {
+        let result = gpx(r#"<gpx><trk><trkseg>
+            <trkpt lat="0" lon="0"><ele>10</ele></trkpt>
+            <trkpt lat="0"><ele>20</ele></trkpt>
+        </trkseg></trk></gpx>"#);
+
+        match result {
+            Err(ElevationProfileError::Parse(message)) => assert!(message.contains("trkpt 2")),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn gpx_without_points_has_too_few() {
+        assert!(matches!(
+            gpx("<gpx></gpx>"),
+            Err(ElevationProfileError::TooFewPoints)
+        ));
+    }
+}
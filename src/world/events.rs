@@ -0,0 +1,9 @@
+use std::path::PathBuf;
+
+use bevy::prelude::Event;
+
+#[derive(Event)]
+pub enum ElevationProfileEvent {
+    Load(PathBuf),
+    Clear,
+}
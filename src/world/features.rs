@@ -0,0 +1,439 @@
+use std::{f64::consts::TAU, sync::Arc};
+
+use avian2d::{math::Vector, prelude::*};
+use bevy::{
+    asset::RenderAssetUsages,
+    color::palettes::css::{DARK_SLATE_GRAY, SIENNA, SLATE_GRAY, TAN},
+    math::DVec2,
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{
+    biomes::Biome,
+    contraptions::{ContraptionKind, PlacedContraption},
+    generators::TerrainGenerator,
+    hazards::HazardKind,
+    seeding::place_seed,
+    surfaces::SurfaceType,
+};
+
+// Each slot of this width holds at most one procedural feature, which keeps
+// placement independent of where chunk boundaries fall
+const FEATURE_SLOT_WIDTH: f64 = 512.0;
+const FEATURE_CHANCE: f64 = 0.35;
+// Keeps feature rolls independent of the surface patch rolls
+const FEATURE_SALT: u64 = 0xFEA7_0000;
+const HAZARD_CHANCE: f64 = 0.4;
+// Seesaws get their own, wider slots so they're rarer than other features
+const CONTRAPTION_SLOT_WIDTH: f64 = 2048.0;
+const CONTRAPTION_CHANCE: f64 = 0.25;
+const CONTRAPTION_SALT: u64 = 0x5EE5_0000;
+const HAZARD_SALT: u64 = 0x4A2A_0000;
+// Depth features extend below the ground so slopes don't leave them floating
+const FOOTING_DEPTH: f64 = 20.0;
+// Rocks smaller than this get knocked about rather than staying put
+const DYNAMIC_ROCK_RADIUS: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureType {
+    Rock,
+    Log,
+    Kicker,
+    Drop,
+    Stairs,
+}
+
+const DEFAULT_FEATURES: &[(FeatureType, f64)] = &[
+    (FeatureType::Rock, 0.3),
+    (FeatureType::Log, 0.2),
+    (FeatureType::Kicker, 0.2),
+    (FeatureType::Drop, 0.15),
+    (FeatureType::Stairs, 0.15),
+];
+
+impl FeatureType {
+    fn pick(palette: &[(FeatureType, f64)], roll: f64) -> FeatureType {
+        let total: f64 = palette.iter().map(|(_, weight)| weight).sum();
+        let mut remaining = roll * total;
+
+        for (feature_type, weight) in palette {
+            if remaining < *weight {
+                return *feature_type;
+            }
+            remaining -= weight;
+        }
+
+        palette
+            .last()
+            .map(|(feature_type, _)| *feature_type)
+            .unwrap_or(FeatureType::Rock)
+    }
+
+    fn random_kind(&self, rng: &mut StdRng) -> FeatureKind {
+        match self {
+            FeatureType::Rock => {
+                let radius = rng.gen_range(6.0..20.0);
+                let center = DVec2::new(radius, radius * 0.7);
+                let corners = 7;
+
+                // Corners on a squashed circle, so the outline stays convex
+                let points = (0..corners)
+                    .map(|i| {
+                        let angle = TAU * (i as f64 + rng.gen_range(-0.3..0.3)) / corners as f64;
+                        center + DVec2::from_angle(angle) * DVec2::new(radius, radius * 0.8)
+                    })
+                    .collect();
+
+                FeatureKind::Rock {
+                    points,
+                    dynamic: radius < DYNAMIC_ROCK_RADIUS,
+                }
+            }
+            FeatureType::Log => FeatureKind::Log {
+                radius: rng.gen_range(6.0..12.0),
+                dynamic: rng.gen_bool(0.5),
+            },
+            FeatureType::Kicker => FeatureKind::Kicker {
+                length: rng.gen_range(80.0..160.0),
+                height: rng.gen_range(20.0..50.0),
+            },
+            FeatureType::Drop => FeatureKind::Drop {
+                run_up: rng.gen_range(100.0..200.0),
+                length: rng.gen_range(60.0..150.0),
+                height: rng.gen_range(20.0..60.0),
+            },
+            FeatureType::Stairs => FeatureKind::Stairs {
+                steps: rng.gen_range(3..=6),
+                step_length: rng.gen_range(30.0..50.0),
+                step_height: rng.gen_range(6.0..12.0),
+            },
+        }
+    }
+}
+
+// Shapes are laid out from the feature's x, with y = 0 at ground level.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeatureKind {
+    // Irregular boulder, partly sunk into the ground
+    Rock {
+        points: Vec<DVec2>,
+        dynamic: bool,
+    },
+    // Log lying across the trail, seen end on
+    Log {
+        radius: f64,
+        dynamic: bool,
+    },
+    // Wedge rising to `height` at its far end
+    Kicker {
+        length: f64,
+        height: f64,
+    },
+    // Run-up onto a ledge that ends in a sheer drop
+    Drop {
+        run_up: f64,
+        length: f64,
+        height: f64,
+    },
+    // Steps up to a peak and back down again
+    Stairs {
+        steps: u32,
+        step_length: f64,
+        step_height: f64,
+    },
+    Block {
+        width: f64,
+        height: f64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlacedFeature {
+    pub x: f64,
+    pub kind: FeatureKind,
+}
+
+impl FeatureKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FeatureKind::Rock { .. } => "Rock",
+            FeatureKind::Log { .. } => "Log",
+            FeatureKind::Kicker { .. } => "Kicker",
+            FeatureKind::Drop { .. } => "Drop",
+            FeatureKind::Stairs { .. } => "Stairs",
+            FeatureKind::Block { .. } => "Block",
+        }
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            FeatureKind::Rock { dynamic, .. } | FeatureKind::Log { dynamic, .. } => *dynamic,
+            _ => false,
+        }
+    }
+
+    // Loose features sit on the ground as they are, built ones follow the slope
+    pub fn follows_slope(&self) -> bool {
+        !matches!(self, FeatureKind::Rock { .. } | FeatureKind::Log { .. })
+    }
+
+    pub fn surface(&self) -> SurfaceType {
+        match self {
+            FeatureKind::Rock { .. } => SurfaceType::Gravel,
+            FeatureKind::Log { .. } => SurfaceType::Dirt,
+            _ => SurfaceType::Asphalt,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            FeatureKind::Rock { .. } => SLATE_GRAY,
+            FeatureKind::Log { .. } => SIENNA,
+            FeatureKind::Kicker { .. } | FeatureKind::Drop { .. } | FeatureKind::Stairs { .. } => {
+                TAN
+            }
+            FeatureKind::Block { .. } => DARK_SLATE_GRAY,
+        }
+        .into()
+    }
+
+    // Convex pieces making up the feature. Logs are round and have none.
+    fn polygons(&self) -> Vec<Vec<DVec2>> {
+        let footing = -FOOTING_DEPTH;
+
+        match self {
+            FeatureKind::Rock { points, .. } => vec![points.clone()],
+            FeatureKind::Log { .. } => vec![],
+            FeatureKind::Kicker { length, height } => vec![vec![
+                DVec2::new(0.0, footing),
+                DVec2::new(*length, footing),
+                DVec2::new(*length, *height),
+                DVec2::new(0.0, 0.0),
+            ]],
+            FeatureKind::Drop {
+                run_up,
+                length,
+                height,
+            } => vec![vec![
+                DVec2::new(0.0, footing),
+                DVec2::new(run_up + length, footing),
+                DVec2::new(run_up + length, *height),
+                DVec2::new(*run_up, *height),
+                DVec2::new(0.0, 0.0),
+            ]],
+            FeatureKind::Stairs {
+                steps,
+                step_length,
+                step_height,
+            } => (0..2 * steps - 1)
+                .map(|i| {
+                    // Step heights rise to the peak then mirror back down
+                    let level = (i + 1).min(2 * steps - 1 - i) as f64;
+                    let left = i as f64 * step_length;
+                    rectangle(left, left + step_length, footing, level * step_height)
+                })
+                .collect(),
+            FeatureKind::Block { width, height } => {
+                vec![rectangle(0.0, *width, footing, *height)]
+            }
+        }
+    }
+
+    // Where the body's origin sits relative to the feature's ground point
+    pub fn origin(&self) -> DVec2 {
+        match self {
+            FeatureKind::Log { radius, .. } => DVec2::new(*radius, *radius),
+            _ => DVec2::ZERO,
+        }
+    }
+
+    pub fn collider(&self) -> Collider {
+        if let FeatureKind::Log { radius, .. } = self {
+            return Collider::circle(*radius);
+        }
+
+        let mut pieces: Vec<Collider> = self
+            .polygons()
+            .into_iter()
+            .filter_map(|polygon| {
+                Collider::convex_hull(polygon.into_iter().map(Vector::from).collect())
+            })
+            .collect();
+
+        if pieces.len() == 1 {
+            pieces.remove(0)
+        } else {
+            Collider::compound(
+                pieces
+                    .into_iter()
+                    .map(|piece| (Position::default(), Rotation::default(), piece))
+                    .collect(),
+            )
+        }
+    }
+
+    pub fn mesh(&self) -> Mesh {
+        if let FeatureKind::Log { radius, .. } = self {
+            return Circle::new(*radius as f32).into();
+        }
+
+        let mut verticies: Vec<[f32; 3]> = vec![];
+        let mut indicies = vec![];
+
+        // Fan triangulation works because every piece is convex
+        for polygon in self.polygons() {
+            let first_vertex = verticies.len() as u32;
+            verticies.extend(
+                polygon
+                    .iter()
+                    .map(|point| [point.x as f32, point.y as f32, 0.0]),
+            );
+
+            for i in 1..polygon.len() as u32 - 1 {
+                indicies.push(first_vertex);
+                indicies.push(first_vertex + i);
+                indicies.push(first_vertex + i + 1);
+            }
+        }
+
+        let normals = vec![[0., 0., 1.]; verticies.len()];
+
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, verticies)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_indices(Indices::U32(indicies))
+    }
+}
+
+// Counter-clockwise, as the mesh winding expects
+fn rectangle(left: f64, right: f64, bottom: f64, top: f64) -> Vec<DVec2> {
+    vec![
+        DVec2::new(left, bottom),
+        DVec2::new(right, bottom),
+        DVec2::new(right, top),
+        DVec2::new(left, top),
+    ]
+}
+
+// Wraps a generator, scattering rocks, logs and jumps along it. Placement is
+// decided per slot from the seed, so the same features reappear whenever a
+// chunk is regenerated.
+pub struct FeatureScatter {
+    seed: u32,
+    inner: Arc<dyn TerrainGenerator>,
+}
+
+impl FeatureScatter {
+    pub fn new(seed: u32, inner: Arc<dyn TerrainGenerator>) -> Self {
+        FeatureScatter { seed, inner }
+    }
+}
+
+impl TerrainGenerator for FeatureScatter {
+    fn height(&self, x: f64) -> f64 {
+        self.inner.height(x)
+    }
+
+    fn surface(&self, x: f64) -> SurfaceType {
+        self.inner.surface(x)
+    }
+
+    fn biome(&self, x: f64) -> Option<Biome> {
+        self.inner.biome(x)
+    }
+
+    fn is_gap(&self, x: f64) -> bool {
+        self.inner.is_gap(x)
+    }
+
+    fn features(&self, start: f64, end: f64) -> Vec<PlacedFeature> {
+        let mut features = self.inner.features(start, end);
+
+        let first_slot = (start / FEATURE_SLOT_WIDTH).floor() as i64;
+        let last_slot = (end / FEATURE_SLOT_WIDTH).ceil() as i64;
+
+        for slot in first_slot..last_slot {
+            let mut rng = StdRng::seed_from_u64(place_seed(self.seed, slot, FEATURE_SALT));
+
+            if rng.gen::<f64>() >= FEATURE_CHANCE {
+                continue;
+            }
+
+            // Leave the far half of the slot clear so features never crowd each other
+            let x = (slot as f64 + rng.gen_range(0.0..0.5)) * FEATURE_SLOT_WIDTH;
+            if !(start..end).contains(&x) {
+                continue;
+            }
+
+            let palette = self
+                .inner
+                .biome(x)
+                .map(|biome| biome.features())
+                .unwrap_or(DEFAULT_FEATURES);
+            let kind = FeatureType::pick(palette, rng.gen()).random_kind(&mut rng);
+
+            features.push(PlacedFeature { x, kind });
+        }
+
+        features
+    }
+
+    fn contraptions(&self, start: f64, end: f64) -> Vec<PlacedContraption> {
+        let mut contraptions = self.inner.contraptions(start, end);
+
+        let first_slot = (start / CONTRAPTION_SLOT_WIDTH).floor() as i64;
+        let last_slot = (end / CONTRAPTION_SLOT_WIDTH).ceil() as i64;
+
+        for slot in first_slot..last_slot {
+            let mut rng = StdRng::seed_from_u64(place_seed(self.seed, slot, CONTRAPTION_SALT));
+
+            if rng.gen::<f64>() >= CONTRAPTION_CHANCE {
+                continue;
+            }
+
+            let x = (slot as f64 + rng.gen_range(0.0..0.5)) * CONTRAPTION_SLOT_WIDTH;
+            if !(start..end).contains(&x) {
+                continue;
+            }
+
+            contraptions.push(PlacedContraption {
+                x,
+                kind: ContraptionKind::Seesaw {
+                    length: rng.gen_range(160.0..260.0),
+                },
+            });
+        }
+
+        contraptions
+    }
+
+    // Rolled from the valley's position, so a valley keeps its hazard
+    // whichever chunk finds it
+    fn hazard(&self, valley_x: f64) -> Option<HazardKind> {
+        if let Some(hazard) = self.inner.hazard(valley_x) {
+            return Some(hazard);
+        }
+
+        let mut rng =
+            StdRng::seed_from_u64(place_seed(self.seed, valley_x.round() as i64, HAZARD_SALT));
+        if rng.gen::<f64>() >= HAZARD_CHANCE {
+            return None;
+        }
+
+        let roll = rng.gen::<f64>();
+        Some(if roll < 0.5 {
+            HazardKind::Puddle
+        } else if roll < 0.8 {
+            HazardKind::Mud
+        } else {
+            HazardKind::Stream {
+                flows_right: rng.gen(),
+            }
+        })
+    }
+}
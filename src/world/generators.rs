@@ -0,0 +1,227 @@
+use std::{f64::consts::TAU, sync::Arc};
+
+use noise::{NoiseFn, Perlin, Simplex};
+
+use super::{
+    biomes::Biome, contraptions::PlacedContraption, features::PlacedFeature, hazards::HazardKind,
+    resources::TerrainSettings, surfaces::SurfaceType,
+};
+
+// Produces the world-space height of the terrain surface at a given x, along
+// with the surface properties found there.
+pub trait TerrainGenerator: Send + Sync {
+    fn height(&self, x: f64) -> f64;
+
+    fn surface(&self, _x: f64) -> SurfaceType {
+        SurfaceType::Dirt
+    }
+
+    fn biome(&self, _x: f64) -> Option<Biome> {
+        None
+    }
+
+    // Gaps have no ground at all, leaving a hole to jump
+    fn is_gap(&self, _x: f64) -> bool {
+        false
+    }
+
+    // Features whose x lies in [start, end)
+    fn features(&self, _start: f64, _end: f64) -> Vec<PlacedFeature> {
+        vec![]
+    }
+
+    // Contraptions whose x lies in [start, end)
+    fn contraptions(&self, _start: f64, _end: f64) -> Vec<PlacedContraption> {
+        vec![]
+    }
+
+    // What fills the valley whose lowest point is at x, if anything
+    fn hazard(&self, _valley_x: f64) -> Option<HazardKind> {
+        None
+    }
+}
+
+// Fractal Brownian motion: octaves of noise summed with rising frequency and
+// falling amplitude, normalised back into [-1, 1].
+fn fbm(settings: &TerrainSettings, x: f64, sample: impl Fn(f64) -> f64) -> f64 {
+    let mut total = 0.0;
+    let mut normalisation = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = settings.frequency;
+
+    for _ in 0..settings.octaves.max(1) {
+        total += amplitude * sample(x * frequency);
+        normalisation += amplitude;
+        amplitude *= settings.gain;
+        frequency *= settings.lacunarity;
+    }
+
+    total / normalisation
+}
+
+pub struct FbmPerlin {
+    perlin: Perlin,
+    settings: TerrainSettings,
+}
+
+impl FbmPerlin {
+    pub fn new(seed: u32, settings: TerrainSettings) -> Self {
+        FbmPerlin {
+            perlin: Perlin::new(seed),
+            settings,
+        }
+    }
+}
+
+impl TerrainGenerator for FbmPerlin {
+    fn height(&self, x: f64) -> f64 {
+        let noise = fbm(&self.settings, x, |x| self.perlin.get([x]));
+        self.settings.amplitude * (noise + 1.0)
+    }
+}
+
+// Sharp crests and wide valleys, good for mountain passes.
+pub struct RidgedMultifractal {
+    perlin: Perlin,
+    settings: TerrainSettings,
+}
+
+impl RidgedMultifractal {
+    pub fn new(seed: u32, settings: TerrainSettings) -> Self {
+        RidgedMultifractal {
+            perlin: Perlin::new(seed),
+            settings,
+        }
+    }
+}
+
+impl TerrainGenerator for RidgedMultifractal {
+    fn height(&self, x: f64) -> f64 {
+        let mut total = 0.0;
+        let mut normalisation = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = self.settings.frequency;
+        let mut weight = 1.0;
+
+        for _ in 0..self.settings.octaves.max(1) {
+            let mut signal = 1.0 - self.perlin.get([x * frequency]).abs();
+            signal *= signal * weight;
+
+            // Detail is only added where the previous octave formed a ridge
+            weight = (signal * 2.0).clamp(0.0, 1.0);
+
+            total += signal * amplitude;
+            normalisation += amplitude;
+            amplitude *= self.settings.gain;
+            frequency *= self.settings.lacunarity;
+        }
+
+        self.settings.amplitude * 2.0 * total / normalisation
+    }
+}
+
+pub struct FbmSimplex {
+    simplex: Simplex,
+    settings: TerrainSettings,
+}
+
+impl FbmSimplex {
+    pub fn new(seed: u32, settings: TerrainSettings) -> Self {
+        FbmSimplex {
+            simplex: Simplex::new(seed),
+            settings,
+        }
+    }
+}
+
+impl TerrainGenerator for FbmSimplex {
+    fn height(&self, x: f64) -> f64 {
+        let noise = fbm(&self.settings, x, |x| self.simplex.get([x, 0.0]));
+        self.settings.amplitude * (noise + 1.0)
+    }
+}
+
+pub struct Flat {
+    pub height: f64,
+}
+
+impl TerrainGenerator for Flat {
+    fn height(&self, _x: f64) -> f64 {
+        self.height
+    }
+}
+
+// Regular hills of a fixed wavelength, useful as a repeatable test course.
+pub struct SineCourse {
+    pub amplitude: f64,
+    pub wavelength: f64,
+}
+
+impl TerrainGenerator for SineCourse {
+    fn height(&self, x: f64) -> f64 {
+        self.amplitude * ((TAU * x / self.wavelength).sin() + 1.0)
+    }
+}
+
+// Height of the walls closing off a course, above the ground they rise from
+const WALL_HEIGHT: f64 = 400.0;
+
+// Wraps a generator, ending the ground in sheer walls outside [left, right].
+// Nothing is placed beyond the walls.
+pub struct Walled {
+    inner: Arc<dyn TerrainGenerator>,
+    left: f64,
+    right: f64,
+}
+
+impl Walled {
+    pub fn new(inner: Arc<dyn TerrainGenerator>, left: f64, right: f64) -> Self {
+        Walled { inner, left, right }
+    }
+
+    fn contains(&self, x: f64) -> bool {
+        (self.left..=self.right).contains(&x)
+    }
+}
+
+impl TerrainGenerator for Walled {
+    // Walls are flat topped, level with WALL_HEIGHT over the ground at the bound
+    fn height(&self, x: f64) -> f64 {
+        let bound = x.clamp(self.left, self.right);
+        if self.contains(x) {
+            self.inner.height(x)
+        } else {
+            self.inner.height(bound) + WALL_HEIGHT
+        }
+    }
+
+    fn surface(&self, x: f64) -> SurfaceType {
+        self.inner.surface(x.clamp(self.left, self.right))
+    }
+
+    fn biome(&self, x: f64) -> Option<Biome> {
+        self.inner.biome(x.clamp(self.left, self.right))
+    }
+
+    fn is_gap(&self, x: f64) -> bool {
+        self.contains(x) && self.inner.is_gap(x)
+    }
+
+    fn features(&self, start: f64, end: f64) -> Vec<PlacedFeature> {
+        let mut features = self.inner.features(start, end);
+        features.retain(|feature| self.contains(feature.x));
+        features
+    }
+
+    fn contraptions(&self, start: f64, end: f64) -> Vec<PlacedContraption> {
+        let mut contraptions = self.inner.contraptions(start, end);
+        contraptions.retain(|contraption| self.contains(contraption.x));
+        contraptions
+    }
+
+    fn hazard(&self, valley_x: f64) -> Option<HazardKind> {
+        self.inner
+            .hazard(valley_x)
+            .filter(|_| self.contains(valley_x))
+    }
+}
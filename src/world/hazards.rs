@@ -0,0 +1,292 @@
+use avian2d::{math::Vector, prelude::*};
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
+
+use crate::bicycle::{
+    frame::BicycleFrame, groupset::Cog, power::EnergyBudget, wheel::BicycleWheel,
+};
+
+use super::{
+    components::TerrainChunkSamples, generators::TerrainGenerator, plugin::WorldTerrainPlugin,
+};
+
+// A sample lower than this many neighbours either side counts as a valley,
+// which keeps small bumps from each getting a puddle
+const VALLEY_WINDOW: usize = 8;
+// Valleys too wide to fill at their depth are left dry
+const MAX_HAZARD_WIDTH: f64 = 800.0;
+// Over the terrain fill, behind the chunk's features (z = 1)
+const HAZARD_Z: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HazardKind {
+    Puddle,
+    // Deeper water flowing along the valley
+    Stream { flows_right: bool },
+    Mud,
+}
+
+impl HazardKind {
+    // How far above the valley floor it fills
+    pub fn depth(&self) -> f64 {
+        match self {
+            HazardKind::Puddle => 20.0,
+            HazardKind::Stream { .. } => 35.0,
+            HazardKind::Mud => 15.0,
+        }
+    }
+
+    // Drag on fully submerged bodies: (per second, per unit of speed per second)
+    fn drag(&self) -> (f64, f64) {
+        match self {
+            HazardKind::Puddle | HazardKind::Stream { .. } => (0.5, 0.005),
+            HazardKind::Mud => (2.0, 0.01),
+        }
+    }
+
+    // Upward push on fully submerged bodies, as a fraction of gravity
+    fn buoyancy(&self) -> f64 {
+        match self {
+            HazardKind::Puddle | HazardKind::Stream { .. } => 0.6,
+            HazardKind::Mud => 0.2,
+        }
+    }
+
+    // How quickly sinking into it slows a wheel's spin, per second
+    fn sink_resistance(&self) -> f64 {
+        match self {
+            HazardKind::Puddle | HazardKind::Stream { .. } => 0.5,
+            HazardKind::Mud => 3.0,
+        }
+    }
+
+    fn flow(&self) -> f64 {
+        match self {
+            HazardKind::Stream { flows_right: true } => 60.0,
+            HazardKind::Stream { flows_right: false } => -60.0,
+            _ => 0.0,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            HazardKind::Puddle | HazardKind::Stream { .. } => Color::srgba(0.2, 0.45, 0.8, 0.6),
+            HazardKind::Mud => Color::srgba(0.3, 0.2, 0.1, 0.85),
+        }
+    }
+}
+
+// Liquid filling a valley up to `level`, in world space
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct LiquidHazard {
+    pub kind: HazardKind,
+    pub left: f64,
+    pub right: f64,
+    pub level: f64,
+}
+
+impl LiquidHazard {
+    // Valleys in a chunk's samples that the generator fills with something
+    pub fn find_in_chunk(
+        chunk_index: i128,
+        samples: &TerrainChunkSamples,
+        generator: &dyn TerrainGenerator,
+    ) -> Vec<LiquidHazard> {
+        let heights = &samples.heights;
+        let start = WorldTerrainPlugin::chunk_sample_start(chunk_index);
+        let width = WorldTerrainPlugin::CHUNK_WIDTH as f64 / samples.surfaces.len() as f64;
+        let sample_x = |i: usize| start + i as f64 * width;
+
+        let mut hazards: Vec<LiquidHazard> = vec![];
+
+        for valley in VALLEY_WINDOW..heights.len().saturating_sub(VALLEY_WINDOW) {
+            let floor = heights[valley];
+            let is_valley = (valley - VALLEY_WINDOW..=valley + VALLEY_WINDOW)
+                .all(|i| i == valley || heights[i] > floor);
+            if !is_valley
+                || hazards
+                    .iter()
+                    .any(|hazard| (hazard.left..=hazard.right).contains(&sample_x(valley)))
+            {
+                continue;
+            }
+
+            let Some(kind) = generator.hazard(sample_x(valley)) else {
+                continue;
+            };
+            let level = floor + kind.depth();
+
+            // Walk out to where the ground rises above the level. Hazards
+            // running off the chunk or into a gap are dropped.
+            let Some(left) = (0..valley).rev().find(|&i| heights[i] >= level) else {
+                continue;
+            };
+            let Some(right) = (valley + 1..heights.len()).find(|&i| heights[i] >= level) else {
+                continue;
+            };
+            if samples.surfaces[left..right].iter().any(Option::is_none) {
+                continue;
+            }
+
+            // Where the ground crosses the level, between samples
+            let crossing = |below: usize, above: usize| {
+                let t = (level - heights[below]) / (heights[above] - heights[below]);
+                sample_x(below) + (sample_x(above) - sample_x(below)) * t
+            };
+            let hazard = LiquidHazard {
+                kind,
+                left: crossing(left + 1, left),
+                right: crossing(right - 1, right),
+                level,
+            };
+
+            if hazard.right - hazard.left <= MAX_HAZARD_WIDTH {
+                hazards.push(hazard);
+            }
+        }
+
+        hazards
+    }
+
+    // Fills between the ground and the level, relative to the chunk
+    pub fn mesh(&self, chunk_index: i128, samples: &TerrainChunkSamples) -> Mesh {
+        let start = WorldTerrainPlugin::chunk_sample_start(chunk_index);
+        let width = WorldTerrainPlugin::CHUNK_WIDTH as f64 / samples.surfaces.len() as f64;
+
+        // Ground points across the hazard, from shore to shore
+        let mut ground = vec![(self.left, self.level)];
+        ground.extend(
+            samples
+                .heights
+                .iter()
+                .enumerate()
+                .map(|(i, height)| (start + i as f64 * width, *height))
+                .filter(|(x, _)| *x > self.left && *x < self.right),
+        );
+        ground.push((self.right, self.level));
+
+        let to_chunk = |x: f64, y: f64| {
+            [
+                (x - start) as f32 - WorldTerrainPlugin::CHUNK_WIDTH / 2.0,
+                y as f32,
+                HAZARD_Z,
+            ]
+        };
+
+        let mut verticies = vec![];
+        let mut indicies = vec![];
+
+        for (x, height) in ground.iter() {
+            let first_vertex = verticies.len() as u32;
+            verticies.push(to_chunk(*x, *height));
+            verticies.push(to_chunk(*x, self.level));
+
+            if first_vertex > 0 {
+                indicies.extend([
+                    first_vertex - 2,
+                    first_vertex,
+                    first_vertex - 1,
+                    first_vertex - 1,
+                    first_vertex,
+                    first_vertex + 1,
+                ]);
+            }
+        }
+
+        let normals = vec![[0., 0., 1.]; verticies.len()];
+
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, verticies)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_indices(Indices::U32(indicies))
+    }
+}
+
+impl WorldTerrainPlugin {
+    // Drag, buoyancy and (for wheels) sink-in resistance on every dynamic body
+    // dipping into a hazard, scaled by how much of it is below the level.
+    // Whatever kinetic energy that takes from the bicycle is booked as its
+    // hazard loss.
+    pub fn apply_liquid_hazards(
+        hazards: Query<&LiquidHazard>,
+        mut bodies: Query<(
+            &RigidBody,
+            &ColliderAabb,
+            &ComputedMass,
+            &ComputedAngularInertia,
+            &mut LinearVelocity,
+            &mut AngularVelocity,
+            Has<BicycleWheel>,
+            Has<BicycleFrame>,
+            Has<Cog>,
+        )>,
+        gravity: Res<Gravity>,
+        time: Res<Time>,
+        mut energy_budget: ResMut<EnergyBudget>,
+    ) {
+        if hazards.is_empty() {
+            return;
+        }
+        let dt = time.delta_secs_f64();
+
+        for (
+            rigid_body,
+            aabb,
+            mass,
+            inertia,
+            mut lin_vel,
+            mut ang_vel,
+            is_wheel,
+            is_frame,
+            is_cog,
+        ) in bodies.iter_mut()
+        {
+            if !rigid_body.is_dynamic() {
+                continue;
+            }
+
+            let center_x = (aabb.min.x + aabb.max.x) / 2.0;
+            let Some(hazard) = hazards
+                .iter()
+                .find(|hazard| (hazard.left..=hazard.right).contains(&center_x))
+            else {
+                continue;
+            };
+
+            let height = (aabb.max.y - aabb.min.y).max(f64::EPSILON);
+            let submerged = ((hazard.level - aabb.min.y) / height).clamp(0.0, 1.0);
+            if submerged == 0.0 {
+                continue;
+            }
+
+            let kinetic = |lin_vel: &LinearVelocity, ang_vel: &AngularVelocity| {
+                0.5 * mass.value() * lin_vel.length_squared()
+                    + 0.5 * inertia.value() * ang_vel.0 * ang_vel.0
+            };
+            let kinetic_before = kinetic(&lin_vel, &ang_vel);
+
+            // Drag acts on speed relative to the liquid, so streams carry things along
+            let flow = Vector::new(hazard.kind.flow(), 0.0);
+            let relative = lin_vel.0 - flow;
+            let (linear, quadratic) = hazard.kind.drag();
+            let slowing = ((linear + quadratic * relative.length()) * submerged * dt).min(1.0);
+            lin_vel.0 = flow + relative * (1.0 - slowing);
+
+            lin_vel.y += gravity.0.length() * hazard.kind.buoyancy() * submerged * dt;
+
+            if is_wheel {
+                ang_vel.0 *= 1.0 - (hazard.kind.sink_resistance() * submerged * dt).min(1.0);
+            }
+
+            if is_wheel || is_frame || is_cog {
+                energy_budget.hazard_loss += kinetic_before - kinetic(&lin_vel, &ang_vel);
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use bevy::{
+    color::palettes::css::{DARK_SLATE_GRAY, SIENNA},
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderRef},
+};
+
+// Terrain fill: a strip of the surface's color along the top, over bands of
+// soil that darken with depth. Meshes carry the surface color as vertex
+// colors and (x, depth below the surface) as uvs.
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct TerrainMaterial {
+    #[uniform(0)]
+    pub topsoil_color: LinearRgba,
+    #[uniform(0)]
+    pub bedrock_color: LinearRgba,
+    // Multiplied over everything, dimmed at night
+    #[uniform(0)]
+    pub light: LinearRgba,
+    #[uniform(0)]
+    pub strip_depth: f32,
+    #[uniform(0)]
+    pub layer_depth: f32,
+    #[uniform(0)]
+    pub layer_count: f32,
+    // Texture repeats per world unit
+    #[uniform(0)]
+    pub texture_scale: f32,
+    // Multiplied over the fill, tiled across the terrain
+    #[texture(1)]
+    #[sampler(2)]
+    pub fill_texture: Option<Handle<Image>>,
+}
+
+impl Default for TerrainMaterial {
+    fn default() -> Self {
+        TerrainMaterial {
+            topsoil_color: SIENNA.into(),
+            bedrock_color: DARK_SLATE_GRAY.into(),
+            light: LinearRgba::WHITE,
+            strip_depth: 12.0,
+            layer_depth: 60.0,
+            layer_count: 5.0,
+            texture_scale: 1.0 / 256.0,
+            fill_texture: None,
+        }
+    }
+}
+
+impl Material for TerrainMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/terrain_material.wgsl".into()
+    }
+}
+
+// Shared by every terrain chunk
+#[derive(Resource)]
+pub struct TerrainMaterialHandle(pub Handle<TerrainMaterial>);
+
+impl FromWorld for TerrainMaterialHandle {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.resource_mut::<Assets<TerrainMaterial>>();
+        TerrainMaterialHandle(materials.add(TerrainMaterial::default()))
+    }
+}
@@ -1,4 +1,16 @@
+pub mod biomes;
+pub mod chunk_cache;
 pub mod components;
+pub mod contraptions;
+pub mod elevation_profile;
+pub mod events;
+pub mod features;
+pub mod generators;
+pub mod hazards;
+pub mod material;
 pub mod plugin;
 pub mod resources;
+pub mod seeding;
+pub mod surfaces;
 pub mod systems;
+pub mod terrain_query;
@@ -1,18 +1,49 @@
 use bevy::prelude::*;
 
-use super::resources::{MaxTerrainChunkCount, TerrainSeed};
+use crate::GameState;
+
+use super::{
+    chunk_cache::{PendingTerrainChunks, TerrainChunkCache},
+    events::ElevationProfileEvent,
+    material::{TerrainMaterial, TerrainMaterialHandle},
+    resources::{
+        ActiveTerrainGenerator, ElevationProfileSource, MaxTerrainChunkCount, PropMaterials,
+        TerrainSeed, TerrainSettings,
+    },
+};
 
 pub struct WorldTerrainPlugin;
 
 impl Plugin for WorldTerrainPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<TerrainSeed>()
+        app.add_plugins(MaterialPlugin::<TerrainMaterial>::default())
+            .init_resource::<TerrainMaterialHandle>()
+            .init_resource::<PropMaterials>()
+            .init_resource::<TerrainSeed>()
+            .init_resource::<TerrainSettings>()
+            .init_resource::<ElevationProfileSource>()
+            .init_resource::<ActiveTerrainGenerator>()
+            .init_resource::<TerrainChunkCache>()
+            .init_resource::<PendingTerrainChunks>()
+            .add_event::<ElevationProfileEvent>()
             .add_systems(
                 Update,
                 (
+                    WorldTerrainPlugin::handle_elevation_profile_event,
+                    WorldTerrainPlugin::rebuild_terrain_generator,
+                    WorldTerrainPlugin::receive_generated_terrain_chunks,
                     WorldTerrainPlugin::generate_surrounding_terrain_chunks,
-                    WorldTerrainPlugin::remove_chunks_outside_viewport,
-                ),
+                    WorldTerrainPlugin::remove_chunks_outside_anchors,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    WorldTerrainPlugin::follow_kinematic_paths,
+                    WorldTerrainPlugin::apply_liquid_hazards,
+                )
+                    .run_if(in_state(GameState::Ready)),
             )
             .init_resource::<MaxTerrainChunkCount>();
     }
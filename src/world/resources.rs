@@ -1,14 +1,57 @@
-use bevy::prelude::Resource;
+use std::sync::Arc;
+
+use bevy::{prelude::*, utils::HashMap};
 use rand::RngCore;
+use strum_macros::EnumIter;
 
-#[derive(Resource)]
+use super::{
+    biomes::BiomeTerrainGenerator,
+    elevation_profile::ElevationProfile,
+    features::FeatureScatter,
+    generators::{FbmPerlin, FbmSimplex, Flat, RidgedMultifractal, SineCourse, TerrainGenerator},
+};
+
+#[derive(Resource, PartialEq)]
 pub struct TerrainSeed(pub u32);
 
 impl Default for TerrainSeed {
     fn default() -> Self {
+        TerrainSeed::random()
+    }
+}
+
+impl TerrainSeed {
+    pub fn random() -> Self {
         let mut rng = rand::thread_rng();
         TerrainSeed(rng.next_u32())
     }
+
+    // Seed passed as `--seed <seed>` or `--seed=<seed>`, if any
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let value = if arg == "--seed" {
+                args.next()
+            } else if let Some(value) = arg.strip_prefix("--seed=") {
+                Some(value.to_string())
+            } else {
+                continue;
+            };
+
+            let Some(value) = value else {
+                return Err("--seed needs a value".to_string());
+            };
+
+            return value
+                .trim()
+                .parse()
+                .map(|seed| Some(TerrainSeed(seed)))
+                .map_err(|_| format!("{:?} is not a valid seed", value));
+        }
+
+        Ok(None)
+    }
 }
 
 #[derive(Resource, PartialEq)]
@@ -19,3 +62,134 @@ impl Default for MaxTerrainChunkCount {
         MaxTerrainChunkCount(4)
     }
 }
+
+#[derive(Debug, EnumIter, PartialEq, Eq, Clone, Copy)]
+pub enum TerrainProfile {
+    FbmPerlin,
+    RidgedMultifractal,
+    Simplex,
+    Flat,
+    SineCourse,
+}
+
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct TerrainSettings {
+    pub profile: TerrainProfile,
+    pub octaves: u32,
+    pub frequency: f64,
+    pub lacunarity: f64,
+    pub gain: f64,
+    pub amplitude: f64,
+    // Vary the profile along the x-axis by biome, using the values above as a base
+    pub biomes: bool,
+    // Scatter rocks, logs, jumps and stairs along the terrain
+    pub features: bool,
+}
+
+impl Default for TerrainSettings {
+    fn default() -> Self {
+        TerrainSettings {
+            profile: TerrainProfile::FbmPerlin,
+            octaves: 4,
+            frequency: 0.0001,
+            lacunarity: 2.0,
+            gain: 0.5,
+            amplitude: 100.0,
+            biomes: true,
+            features: true,
+        }
+    }
+}
+
+impl TerrainSettings {
+    pub fn build_generator(&self, seed: u32) -> Arc<dyn TerrainGenerator> {
+        let generator = self.build_profile_generator(seed);
+
+        if self.features {
+            Arc::new(FeatureScatter::new(seed, generator))
+        } else {
+            generator
+        }
+    }
+
+    fn build_profile_generator(&self, seed: u32) -> Arc<dyn TerrainGenerator> {
+        if self.biomes {
+            return Arc::new(BiomeTerrainGenerator::new(seed, self));
+        }
+
+        match self.profile {
+            TerrainProfile::FbmPerlin => Arc::new(FbmPerlin::new(seed, self.clone())),
+            TerrainProfile::RidgedMultifractal => {
+                Arc::new(RidgedMultifractal::new(seed, self.clone()))
+            }
+            TerrainProfile::Simplex => Arc::new(FbmSimplex::new(seed, self.clone())),
+            TerrainProfile::Flat => Arc::new(Flat {
+                height: self.amplitude,
+            }),
+            TerrainProfile::SineCourse => Arc::new(SineCourse {
+                amplitude: self.amplitude,
+                wavelength: 1.0 / self.frequency,
+            }),
+        }
+    }
+}
+
+// Real-world profile the terrain follows instead of TerrainSettings, if one is loaded.
+#[derive(Resource, Default)]
+pub struct ElevationProfileSource {
+    pub profile: Option<Arc<ElevationProfile>>,
+    pub error: Option<String>,
+}
+
+impl ElevationProfileSource {
+    pub fn generator(&self) -> Option<Arc<dyn TerrainGenerator>> {
+        self.profile
+            .clone()
+            .map(|profile| profile as Arc<dyn TerrainGenerator>)
+    }
+}
+
+// Generator built from the current TerrainSettings and TerrainSeed.
+#[derive(Resource)]
+pub struct ActiveTerrainGenerator(pub Arc<dyn TerrainGenerator>);
+
+impl FromWorld for ActiveTerrainGenerator {
+    fn from_world(world: &mut World) -> Self {
+        let seed = world.get_resource_or_insert_with(TerrainSeed::default).0;
+        let settings = world.get_resource_or_insert_with(TerrainSettings::default);
+        ActiveTerrainGenerator(settings.build_generator(seed))
+    }
+}
+
+// Unlit materials for the features and other props on terrain chunks, one
+// for each color, shared by every chunk that uses it
+#[derive(Resource, Default)]
+pub struct PropMaterials {
+    handles: HashMap<([u8; 4], bool), Handle<StandardMaterial>>,
+}
+
+impl PropMaterials {
+    // Blended materials let the color's alpha show what's behind
+    pub fn get(
+        &mut self,
+        materials: &mut Assets<StandardMaterial>,
+        color: Color,
+        blend: bool,
+    ) -> Handle<StandardMaterial> {
+        self.handles
+            .entry((color.to_srgba().to_u8_array(), blend))
+            .or_insert_with(|| {
+                materials.add(StandardMaterial {
+                    base_color: color,
+                    alpha_mode: if blend {
+                        AlphaMode::Blend
+                    } else {
+                        AlphaMode::Opaque
+                    },
+                    unlit: true,
+                    ..Default::default()
+                })
+            })
+            .clone()
+    }
+}
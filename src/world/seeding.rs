@@ -0,0 +1,14 @@
+// splitmix64's step, so inputs that differ by a bit give unrelated outputs
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// Rng seed for a roll made at `place` along the terrain, such as a patch or
+// slot index. Each input is mixed in turn, so neighbouring places, negative
+// places and different salts never give related seeds.
+pub fn place_seed(seed: u32, place: i64, salt: u64) -> u64 {
+    splitmix64(splitmix64(splitmix64(seed as u64) ^ place as u64) ^ salt)
+}
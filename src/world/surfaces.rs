@@ -0,0 +1,104 @@
+use avian2d::prelude::{CoefficientCombine, Friction};
+use bevy::{
+    color::palettes::css::{BURLYWOOD, DARK_GRAY, DIM_GRAY, LIGHT_CYAN, PERU, SADDLE_BROWN},
+    prelude::*,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumIter;
+
+use super::seeding::place_seed;
+
+// Surfaces are picked per patch rather than per segment so that they form runs
+pub const SURFACE_PATCH_WIDTH: f64 = 400.0;
+
+#[derive(Debug, EnumIter, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum SurfaceType {
+    Asphalt,
+    Gravel,
+    Dirt,
+    Mud,
+    Ice,
+    Sand,
+}
+
+impl SurfaceType {
+    // Sliding (dynamic) friction coefficient
+    pub fn friction(&self) -> f64 {
+        match self {
+            SurfaceType::Asphalt => 0.9,
+            SurfaceType::Gravel => 0.7,
+            SurfaceType::Dirt => 0.8,
+            SurfaceType::Mud => 0.45,
+            SurfaceType::Ice => 0.08,
+            SurfaceType::Sand => 0.55,
+        }
+    }
+
+    // Static friction coefficient, how much force the tire takes before it breaks loose
+    pub fn grip(&self) -> f64 {
+        match self {
+            SurfaceType::Asphalt => 1.0,
+            SurfaceType::Gravel => 0.8,
+            SurfaceType::Dirt => 0.9,
+            SurfaceType::Mud => 0.5,
+            SurfaceType::Ice => 0.1,
+            SurfaceType::Sand => 0.6,
+        }
+    }
+
+    pub fn rolling_resistance(&self) -> f64 {
+        match self {
+            SurfaceType::Asphalt => 0.004,
+            SurfaceType::Gravel => 0.012,
+            SurfaceType::Dirt => 0.015,
+            SurfaceType::Mud => 0.05,
+            SurfaceType::Ice => 0.003,
+            SurfaceType::Sand => 0.06,
+        }
+    }
+
+    pub fn color(&self) -> LinearRgba {
+        match self {
+            SurfaceType::Asphalt => DIM_GRAY,
+            SurfaceType::Gravel => DARK_GRAY,
+            SurfaceType::Dirt => PERU,
+            SurfaceType::Mud => SADDLE_BROWN,
+            SurfaceType::Ice => LIGHT_CYAN,
+            SurfaceType::Sand => BURLYWOOD,
+        }
+        .into()
+    }
+
+    // Multiply so the surface coefficient is used as-is against the tire's 1.0
+    pub fn physics_friction(&self) -> Friction {
+        Friction::new(self.friction())
+            .with_static_coefficient(self.grip())
+            .with_combine_rule(CoefficientCombine::Multiply)
+    }
+
+    // Weighted pick from a palette of (surface, weight) using roll in [0, 1)
+    pub fn pick(palette: &[(SurfaceType, f64)], roll: f64) -> SurfaceType {
+        let total: f64 = palette.iter().map(|(_, weight)| weight).sum();
+        let mut remaining = roll * total;
+
+        for (surface, weight) in palette {
+            if remaining < *weight {
+                return *surface;
+            }
+            remaining -= weight;
+        }
+
+        palette
+            .last()
+            .map(|(surface, _)| *surface)
+            .unwrap_or(SurfaceType::Dirt)
+    }
+}
+
+// Deterministic uniform roll in [0, 1) for a patch of terrain
+pub fn patch_roll(seed: u32, x: f64, salt: u64) -> f64 {
+    let patch = (x / SURFACE_PATCH_WIDTH).floor() as i64;
+    let mut rng = StdRng::seed_from_u64(place_seed(seed, patch, salt));
+    rng.gen::<f64>()
+}
@@ -1,35 +1,158 @@
+use std::{collections::BTreeSet, sync::Arc};
+
+use crate::{
+    bicycle::frame::BicycleFrame, course::resources::ActiveCourse, track::resources::ActiveTrack,
+    GameLayer,
+};
 use avian2d::{math::Vector, prelude::*};
 use bevy::{
     asset::RenderAssetUsages,
-    color::palettes::css::LIGHT_GREEN,
     prelude::*,
     render::mesh::{Indices, PrimitiveTopology},
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool},
 };
-use noise::{NoiseFn, Perlin};
-
-use crate::{camera::components::FollowCamera, GameLayer};
 
 use super::{
-    components::{Terrain, TerrainChunk},
+    chunk_cache::{CachedTerrainChunk, PendingTerrainChunks, TerrainChunkCache, TerrainChunkData},
+    components::{
+        Terrain, TerrainChunk, TerrainChunkSamples, TerrainDetail, TerrainFeature,
+        TerrainStreamingAnchor, TerrainSurface,
+    },
+    contraptions::{ContraptionKind, ContraptionLayout},
+    elevation_profile::ElevationProfile,
+    events::ElevationProfileEvent,
+    features::PlacedFeature,
+    generators::{TerrainGenerator, Walled},
+    hazards::LiquidHazard,
+    material::TerrainMaterialHandle,
     plugin::WorldTerrainPlugin,
-    resources::{MaxTerrainChunkCount, TerrainSeed},
+    resources::{
+        ActiveTerrainGenerator, ElevationProfileSource, MaxTerrainChunkCount, PropMaterials,
+        TerrainSeed, TerrainSettings,
+    },
+    surfaces::SurfaceType,
 };
 
 impl WorldTerrainPlugin {
     pub const CHUNK_WIDTH: f32 = 2048.0;
     pub const SUBSTEP_COUNT: u32 = 20;
+    // ~8 units per segment, fine enough for bumps smaller than a wheel
+    pub const NEAR_SUBSTEP_COUNT: u32 = 256;
+    // Chunks this many either side of the bicycle's get Near detail
+    pub const NEAR_CHUNK_RADIUS: i128 = 1;
 
     pub fn x_pos_to_chunk_index(pos: f64) -> i128 {
         (pos / Self::CHUNK_WIDTH as f64).round() as i128
     }
 
+    pub fn handle_elevation_profile_event(
+        mut events: EventReader<ElevationProfileEvent>,
+        mut profile_source: ResMut<ElevationProfileSource>,
+    ) {
+        for evt in events.read() {
+            match evt {
+                ElevationProfileEvent::Load(path) => match ElevationProfile::load(path) {
+                    Ok(profile) => {
+                        info!(
+                            "Loaded elevation profile {:?}: {:.0}m long, {:.0}m of climbing",
+                            path,
+                            profile.length(),
+                            profile.elevation_gain()
+                        );
+                        profile_source.profile = Some(Arc::new(profile));
+                        profile_source.error = None;
+                    }
+                    Err(err) => {
+                        error!("Failed to load elevation profile {:?}: {}", path, err);
+                        // A failed load leaves the terrain as it was, so don't trigger a rebuild
+                        profile_source.bypass_change_detection().error = Some(format!("{}", err));
+                    }
+                },
+                ElevationProfileEvent::Clear => {
+                    profile_source.profile = None;
+                    profile_source.error = None;
+                }
+            }
+        }
+    }
+
+    pub fn rebuild_terrain_generator(
+        mut commands: Commands,
+        terrain_settings: Res<TerrainSettings>,
+        terrain_seed: Res<TerrainSeed>,
+        profile_source: Res<ElevationProfileSource>,
+        active_track: Res<ActiveTrack>,
+        active_course: Res<ActiveCourse>,
+        mut terrain_generator: ResMut<ActiveTerrainGenerator>,
+        mut chunk_cache: ResMut<TerrainChunkCache>,
+        mut pending_chunks: ResMut<PendingTerrainChunks>,
+        terrain_chunks: Query<Entity, With<TerrainChunk>>,
+    ) {
+        if terrain_settings.is_added()
+            || !(terrain_settings.is_changed()
+                || terrain_seed.is_changed()
+                || profile_source.is_changed()
+                || active_track.is_changed()
+                || active_course.is_changed())
+        {
+            return;
+        }
+
+        info!("Rebuilding Terrain Generator");
+        // A hand-authored track takes priority over a real-world profile
+        let generator = active_track
+            .generator()
+            .or_else(|| profile_source.generator())
+            .unwrap_or_else(|| terrain_settings.build_generator(terrain_seed.0));
+
+        terrain_generator.0 = match active_course.course {
+            Some(course) => {
+                let (left, right) = course.bounds();
+                Arc::new(Walled::new(generator, left, right))
+            }
+            None => generator,
+        };
+
+        // Existing chunks were built by the old generator, so regenerate them all.
+        // Dropping the pending tasks cancels them.
+        chunk_cache.clear();
+        pending_chunks.0.clear();
+        for chunk in terrain_chunks.iter() {
+            commands.entity(chunk).despawn_recursive();
+        }
+    }
+
+    // Collect chunks finished on the task pool, ready to be spawned
+    pub fn receive_generated_terrain_chunks(
+        mut pending_chunks: ResMut<PendingTerrainChunks>,
+        mut chunk_cache: ResMut<TerrainChunkCache>,
+        mut meshes: ResMut<Assets<Mesh>>,
+    ) {
+        pending_chunks
+            .0
+            .retain(|key, task| match block_on(future::poll_once(task)) {
+                Some(chunk_data) => {
+                    chunk_cache.insert(*key, chunk_data.add_meshes(&mut meshes));
+                    false
+                }
+                None => true,
+            });
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn generate_surrounding_terrain_chunks(
         mut commands: Commands,
-        camera: Query<&Transform, With<FollowCamera>>,
-        terrain_chunks: Query<&TerrainChunk>,
+        anchors: Query<(&GlobalTransform, &TerrainStreamingAnchor)>,
+        frame: Query<&Transform, With<BicycleFrame>>,
+        terrain_chunks: Query<(Entity, &TerrainChunk, &TerrainDetail)>,
         terrain: Query<(Entity, Option<&Children>), With<Terrain>>,
+        terrain_generator: Res<ActiveTerrainGenerator>,
         terrain_seed: Res<TerrainSeed>,
         terrain_chunk_count: Res<MaxTerrainChunkCount>,
+        mut chunk_cache: ResMut<TerrainChunkCache>,
+        mut pending_chunks: ResMut<PendingTerrainChunks>,
+        terrain_material: Res<TerrainMaterialHandle>,
+        mut prop_materials: ResMut<PropMaterials>,
         mut meshes: ResMut<Assets<Mesh>>,
         mut materials: ResMut<Assets<StandardMaterial>>,
     ) {
@@ -45,121 +168,388 @@ impl WorldTerrainPlugin {
 
         // Parent Should Exist, So Get Parent Terrain Entity
         if let Ok((terrain_id, _terrain_chunks_query)) = terrain.get_single() {
-            let camera_t = camera.single();
-            let camera_t_x = camera_t.translation.x;
-            let chunk_index = Self::x_pos_to_chunk_index(camera_t_x as f64);
-
-            // info!("Camera_t current chunk index: {:?}", chunk_index);
-            // info!("Seed: {:?}", terrain_seed.0);
-
-            for index in chunk_index - terrain_chunk_count.0 as i128 / 2
-                ..chunk_index + (terrain_chunk_count.0 as i128 / 2)
-            {
-                // If the chunk doesn't exist
-                if !terrain_chunks.iter().any(|chunk| chunk.0 == index) {
-                    info!("Creating Chunk {:?}", index);
-
-                    let (chunk_collider, chunk_mesh) =
-                        WorldTerrainPlugin::generate_hilly_terrain_chunk(index, terrain_seed.0);
-
-                    commands.entity(terrain_id).with_child((
-                        Name::new(format!("TerrainChunk({:?})", index)),
-                        TerrainChunk(index),
+            // Detail follows the bicycle. Before it spawns nothing is near.
+            let bicycle_chunk_index = frame
+                .get_single()
+                .ok()
+                .map(|frame_t| Self::x_pos_to_chunk_index(frame_t.translation.x as f64));
+
+            for index in Self::streamed_chunk_indices(&anchors, &terrain_chunk_count) {
+                let is_near = bicycle_chunk_index.is_some_and(|bicycle_chunk_index| {
+                    (index - bicycle_chunk_index).abs() <= Self::NEAR_CHUNK_RADIUS
+                });
+                let detail = if is_near {
+                    TerrainDetail::Near
+                } else {
+                    TerrainDetail::Far
+                };
+
+                // An existing chunk at the wrong detail stays until its
+                // replacement is ready, so the ground never disappears
+                let existing = terrain_chunks.iter().find(|(_, chunk, _)| chunk.0 == index);
+                if existing.is_some_and(|(_, _, existing_detail)| *existing_detail == detail) {
+                    continue;
+                }
+
+                let key = (terrain_seed.0, index, detail);
+
+                let chunk_data = match chunk_cache.get(&key) {
+                    Some(chunk_data) => chunk_data,
+                    // The ground under the rider can't wait for the task pool
+                    None if Some(index) == bicycle_chunk_index => {
+                        info!("Creating Chunk {:?} ({:?})", index, detail);
+                        pending_chunks.0.remove(&key);
+                        chunk_cache.insert(
+                            key,
+                            Self::build_terrain_chunk(index, detail, terrain_generator.0.as_ref())
+                                .add_meshes(&mut meshes),
+                        )
+                    }
+                    None => {
+                        if !pending_chunks.0.contains_key(&key) {
+                            info!("Queueing Chunk {:?} ({:?})", index, detail);
+                            let generator = terrain_generator.0.clone();
+                            let task = AsyncComputeTaskPool::get().spawn(async move {
+                                Self::build_terrain_chunk(index, detail, generator.as_ref())
+                            });
+                            pending_chunks.0.insert(key, task);
+                        }
+                        continue;
+                    }
+                };
+
+                if let Some((existing_id, _, _)) = existing {
+                    commands.entity(existing_id).despawn_recursive();
+                }
+
+                Self::spawn_terrain_chunk(
+                    &mut commands,
+                    terrain_id,
+                    index,
+                    detail,
+                    &chunk_data,
+                    &terrain_material,
+                    &mut prop_materials,
+                    &mut meshes,
+                    &mut materials,
+                );
+            }
+        }
+    }
+
+    pub fn build_terrain_chunk(
+        chunk_index: i128,
+        detail: TerrainDetail,
+        generator: &dyn TerrainGenerator,
+    ) -> TerrainChunkData {
+        let (samples, mesh) = Self::generate_hilly_terrain_chunk(chunk_index, detail, generator);
+
+        // Far chunks are out of the bicycle's reach, so they're only drawn
+        let (surface_colliders, features, contraptions, hazards) = match detail {
+            TerrainDetail::Near => (
+                Self::generate_surface_colliders(&samples),
+                Self::place_chunk_features(chunk_index, &samples, generator)
+                    .into_iter()
+                    .map(|(feature, transform)| {
+                        let (collider, mesh) = (feature.kind.collider(), feature.kind.mesh());
+                        (feature, transform, collider, mesh)
+                    })
+                    .collect(),
+                Self::place_chunk_contraptions(chunk_index, generator),
+                LiquidHazard::find_in_chunk(chunk_index, &samples, generator)
+                    .into_iter()
+                    .map(|hazard| (hazard, hazard.mesh(chunk_index, &samples)))
+                    .collect(),
+            ),
+            TerrainDetail::Far => (vec![], vec![], vec![], vec![]),
+        };
+
+        TerrainChunkData {
+            samples,
+            mesh,
+            surface_colliders,
+            features,
+            contraptions,
+            hazards,
+        }
+    }
+
+    fn spawn_terrain_chunk(
+        commands: &mut Commands,
+        terrain_id: Entity,
+        index: i128,
+        detail: TerrainDetail,
+        chunk_data: &CachedTerrainChunk,
+        terrain_material: &TerrainMaterialHandle,
+        prop_materials: &mut PropMaterials,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+    ) {
+        commands
+            .spawn((
+                Name::new(format!("TerrainChunk({:?})", index)),
+                TerrainChunk(index),
+                detail,
+                RigidBody::Static,
+                Mesh3d(chunk_data.mesh.clone()),
+                MeshMaterial3d(terrain_material.0.clone()),
+                // Wireframe,
+                Transform::from_xyz((index as f32).round() * Self::CHUNK_WIDTH, 0.0, 10.0),
+                chunk_data.samples.clone(),
+            ))
+            .set_parent(terrain_id)
+            .with_children(|chunk| {
+                for (surface, collider, transform) in chunk_data.surface_colliders.iter() {
+                    chunk.spawn((
+                        Name::new(format!("{:?} Surface", surface)),
+                        TerrainSurface(*surface),
                         CollisionLayers::new(
                             [GameLayer::World],
-                            [GameLayer::Wheels, GameLayer::Frame],
+                            [GameLayer::Wheels, GameLayer::Frame, GameLayer::Props],
                         ),
-                        RigidBody::Static,
                         // CollisionMargin(1.0),
-                        chunk_collider,
-                        Friction::new(1.0),
+                        collider.clone(),
+                        surface.physics_friction(),
                         Restitution::new(0.0),
                         // SweptCcd::default(),
-                        Mesh3d(meshes.add(chunk_mesh)),
-                        MeshMaterial3d(materials.add(StandardMaterial {
-                            base_color: LIGHT_GREEN.into(),
-                            unlit: true,
-                            ..Default::default()
-                        })),
-                        // Wireframe,
-                        Transform::from_xyz((index as f32).round() * Self::CHUNK_WIDTH, 0.0, 10.0),
+                        *transform,
                     ));
                 }
-            }
-        }
+
+                for (feature, transform, collider, mesh) in chunk_data.features.iter() {
+                    let rigid_body = if feature.kind.is_dynamic() {
+                        RigidBody::Dynamic
+                    } else {
+                        RigidBody::Static
+                    };
+
+                    chunk.spawn((
+                        Name::new(feature.kind.name()),
+                        TerrainFeature,
+                        rigid_body,
+                        collider.clone(),
+                        CollisionLayers::new(
+                            [GameLayer::Props],
+                            [
+                                GameLayer::World,
+                                GameLayer::Wheels,
+                                GameLayer::Frame,
+                                GameLayer::Props,
+                            ],
+                        ),
+                        feature.kind.surface().physics_friction(),
+                        Restitution::new(0.0),
+                        Mesh3d(mesh.clone()),
+                        MeshMaterial3d(prop_materials.get(materials, feature.kind.color(), false)),
+                        *transform,
+                    ));
+                }
+
+                for contraption in chunk_data.contraptions.iter() {
+                    contraption.spawn(chunk, index, meshes, prop_materials, materials);
+                }
+
+                for (hazard, mesh) in chunk_data.hazards.iter() {
+                    chunk.spawn((
+                        Name::new(format!("{:?} Hazard", hazard.kind)),
+                        *hazard,
+                        Mesh3d(mesh.clone()),
+                        MeshMaterial3d(prop_materials.get(materials, hazard.kind.color(), true)),
+                        Transform::default(),
+                    ));
+                }
+            });
     }
 
-    pub fn terrain_height_sample(x_pos: f64, seed: u32) -> f64 {
-        let perlin = Perlin::new(seed);
-        100.0 * (perlin.get([0.0001 * x_pos]) + 1.0) / Self::CHUNK_WIDTH as f64
+    // x of the first height sample taken for a chunk. Chunks are centered on
+    // their index, so this is the left edge of the chunk.
+    pub fn chunk_sample_start(chunk_index: i128) -> f64 {
+        (chunk_index as f64 - 0.5) * Self::CHUNK_WIDTH as f64
     }
 
-    fn substep_width() -> f64 {
-        Self::CHUNK_WIDTH as f64 / Self::SUBSTEP_COUNT as f64
+    pub fn substep_count(detail: TerrainDetail) -> u32 {
+        match detail {
+            TerrainDetail::Near => Self::NEAR_SUBSTEP_COUNT,
+            TerrainDetail::Far => Self::SUBSTEP_COUNT,
+        }
+    }
+
+    // Width of each segment between the samples
+    fn substep_width(samples: &TerrainChunkSamples) -> f64 {
+        Self::CHUNK_WIDTH as f64 / samples.surfaces.len() as f64
     }
 
-    pub fn generate_hilly_terrain_chunk(chunk_index: i128, seed: u32) -> (Collider, Mesh) {
-        let mut terrain_height_samples = vec![];
+    pub fn generate_hilly_terrain_chunk(
+        chunk_index: i128,
+        detail: TerrainDetail,
+        generator: &dyn TerrainGenerator,
+    ) -> (TerrainChunkSamples, Mesh) {
+        let substep_count = Self::substep_count(detail);
+        let substep_width = Self::CHUNK_WIDTH as f64 / substep_count as f64;
+        let mut heights = vec![];
+        let mut surfaces = vec![];
 
         // Sample Points via Terrain Generation Function
-        for i in 0..=Self::SUBSTEP_COUNT {
-            let x = (chunk_index as f64 * Self::CHUNK_WIDTH as f64).floor()
-                + Self::substep_width() * i as f64;
-            let sample_point = Self::terrain_height_sample(x, seed);
+        for i in 0..=substep_count {
+            let x = Self::chunk_sample_start(chunk_index) + substep_width * i as f64;
+            heights.push(generator.height(x));
+
+            // Each segment takes the surface found at its midpoint
+            if i < substep_count {
+                let midpoint = x + substep_width / 2.0;
+                surfaces.push((!generator.is_gap(midpoint)).then(|| generator.surface(midpoint)));
+            }
+        }
 
-            terrain_height_samples.push(sample_point);
+        let samples = TerrainChunkSamples { heights, surfaces };
+        let mesh = Self::generate_terrain_mesh(&samples);
+
+        (samples, mesh)
+    }
+
+    // One heightfield collider per run of segments sharing a surface, so each
+    // run can carry its own friction. Gaps get no collider. Transforms are
+    // relative to the chunk.
+    pub fn generate_surface_colliders(
+        samples: &TerrainChunkSamples,
+    ) -> Vec<(SurfaceType, Collider, Transform)> {
+        let mut colliders = vec![];
+        let mut run_start = 0;
+        let substep_width = Self::substep_width(samples);
+
+        for i in 1..=samples.surfaces.len() {
+            if i < samples.surfaces.len() && samples.surfaces[i] == samples.surfaces[run_start] {
+                continue;
+            }
+
+            let run_width = (i - run_start) as f64 * substep_width;
+            let run_center =
+                (run_start + i) as f64 / 2.0 * substep_width - Self::CHUNK_WIDTH as f64 / 2.0;
+
+            let Some(surface) = samples.surfaces[run_start] else {
+                run_start = i;
+                continue;
+            };
+
+            colliders.push((
+                surface,
+                Collider::heightfield(
+                    samples.heights[run_start..=i].to_vec(),
+                    Vector::new(run_width, 1.0),
+                ),
+                Transform::from_xyz(run_center as f32, 0.0, 0.0),
+            ));
+
+            run_start = i;
         }
 
-        let heightfield_collider = Collider::heightfield(
-            terrain_height_samples.clone().into_iter().collect(),
-            Vector::splat(Self::CHUNK_WIDTH as f64),
-        );
+        colliders
+    }
 
-        let mesh = Self::generate_terrain_mesh(&terrain_height_samples);
+    // Features sitting on the chunk's sampled surface, with transforms relative
+    // to the chunk. Features over a gap are dropped.
+    pub fn place_chunk_features(
+        chunk_index: i128,
+        samples: &TerrainChunkSamples,
+        generator: &dyn TerrainGenerator,
+    ) -> Vec<(PlacedFeature, Transform)> {
+        let start = Self::chunk_sample_start(chunk_index);
+        let end = start + Self::CHUNK_WIDTH as f64;
+        let substep_width = Self::substep_width(samples);
+
+        generator
+            .features(start, end)
+            .into_iter()
+            .filter_map(|feature| {
+                let position = (feature.x - start) / substep_width;
+                let segment = (position.floor() as usize).min(samples.surfaces.len() - 1);
+                samples.surfaces[segment]?;
+
+                // Linear between samples, matching the heightfield collider
+                let (left, right) = (samples.heights[segment], samples.heights[segment + 1]);
+                let ground = left + (right - left) * (position - segment as f64);
+                let slope = ((right - left) / substep_width).atan();
+
+                let origin = feature.kind.origin();
+                let mut transform = Transform::from_xyz(
+                    (feature.x - start + origin.x) as f32 - Self::CHUNK_WIDTH / 2.0,
+                    (ground + origin.y) as f32,
+                    1.0,
+                );
+                if feature.kind.follows_slope() {
+                    transform.rotate_z(slope as f32);
+                }
 
-        (heightfield_collider, mesh)
+                Some((feature, transform))
+            })
+            .collect()
     }
 
-    pub fn generate_terrain_mesh(terrain_height_samples: &[f64]) -> Mesh {
+    // Contraptions starting in the chunk. Bridges are hung across gaps, but
+    // anything else starting over one is dropped.
+    pub fn place_chunk_contraptions(
+        chunk_index: i128,
+        generator: &dyn TerrainGenerator,
+    ) -> Vec<ContraptionLayout> {
+        let start = Self::chunk_sample_start(chunk_index);
+        let end = start + Self::CHUNK_WIDTH as f64;
+
+        generator
+            .contraptions(start, end)
+            .iter()
+            .filter(|contraption| {
+                matches!(contraption.kind, ContraptionKind::Bridge { .. })
+                    || !generator.is_gap(contraption.x)
+            })
+            .map(|contraption| ContraptionLayout::new(contraption, generator))
+            .collect()
+    }
+
+    pub fn generate_terrain_mesh(samples: &TerrainChunkSamples) -> Mesh {
         let mut verticies: Vec<[f32; 3]> = vec![];
         let mut indicies = vec![];
         let mut normals = vec![];
-
-        let sample_heights = terrain_height_samples.iter().enumerate().peekable();
-        for (i, height) in sample_heights {
-            let height = *height as f32;
-
-            verticies.push([
-                (i as f32 * Self::substep_width() as f32) - Self::CHUNK_WIDTH / 2.0,
-                -1000.0,
-                0.0,
-            ]); // Vertex 0
-            verticies.push([
-                (i as f32 * Self::substep_width() as f32) - Self::CHUNK_WIDTH / 2.0,
-                (height * Self::CHUNK_WIDTH) - 1.0,
-                0.0,
-            ]);
-
-            normals.push([0., 0., 1.]);
-            normals.push([0., 0., 1.]);
-        }
-
-        for step in 0..Self::SUBSTEP_COUNT {
-            let i = step * 2;
-            // info!("STEP: {}", step);
+        let mut vertex_colors = vec![];
+        let mut uvs = vec![];
+        let substep_width = Self::substep_width(samples) as f32;
+
+        // Segments don't share vertices so surface colors stay crisp at the edges
+        for (segment, surface) in samples.surfaces.iter().enumerate() {
+            let Some(surface) = surface else {
+                continue;
+            };
+            let color = surface.color().to_f32_array();
+            let first_vertex = verticies.len() as u32;
+
+            for i in [segment, segment + 1] {
+                let x = (i as f32 * substep_width) - Self::CHUNK_WIDTH / 2.0;
+                let height = samples.heights[i] as f32;
+
+                verticies.push([x, -1000.0, 0.0]);
+                verticies.push([x, height - 1.0, 0.0]);
+
+                // TerrainMaterial layers the fill by depth below the surface
+                let uv_x = x + Self::CHUNK_WIDTH / 2.0;
+                uvs.push([uv_x, height - 1.0 + 1000.0]);
+                uvs.push([uv_x, 0.0]);
+
+                normals.push([0., 0., 1.]);
+                normals.push([0., 0., 1.]);
+
+                vertex_colors.push(color);
+                vertex_colors.push(color);
+            }
 
             // First Triangle
-            indicies.push(i);
-            indicies.push(i + 2);
-            indicies.push(i + 1);
+            indicies.push(first_vertex);
+            indicies.push(first_vertex + 2);
+            indicies.push(first_vertex + 1);
 
             // Second Triangle
-            indicies.push(i + 1);
-            indicies.push(i + 2);
-            indicies.push(i + 3);
+            indicies.push(first_vertex + 1);
+            indicies.push(first_vertex + 2);
+            indicies.push(first_vertex + 3);
         }
-        // info!("Vertex Count: {}", verticies.len());
-        // info!("Index Count: {}", indicies.len());
 
         Mesh::new(
             PrimitiveTopology::TriangleList,
@@ -167,39 +557,39 @@ impl WorldTerrainPlugin {
         )
         .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, verticies)
         .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, vertex_colors)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
         .with_inserted_indices(Indices::U32(indicies))
     }
 
-    pub fn remove_chunks_outside_viewport(
+    // Every chunk within range of at least one streaming anchor
+    pub fn streamed_chunk_indices(
+        anchors: &Query<(&GlobalTransform, &TerrainStreamingAnchor)>,
+        terrain_chunk_count: &MaxTerrainChunkCount,
+    ) -> BTreeSet<i128> {
+        anchors
+            .iter()
+            .flat_map(|(anchor_gt, anchor)| {
+                let anchor_index = Self::x_pos_to_chunk_index(anchor_gt.translation().x as f64);
+                let radius = anchor.chunk_radius(terrain_chunk_count);
+                anchor_index - radius..=anchor_index + radius
+            })
+            .collect()
+    }
+
+    pub fn remove_chunks_outside_anchors(
         mut commands: Commands,
-        camera_viewport: Query<(&Camera, &GlobalTransform), With<FollowCamera>>,
+        anchors: Query<(&GlobalTransform, &TerrainStreamingAnchor)>,
         terrain_chunks: Query<(Entity, &TerrainChunk), With<TerrainChunk>>,
         terrain: Query<Entity, With<Terrain>>,
         terrain_chunk_count: Res<MaxTerrainChunkCount>,
     ) {
-        let (camera, camera_gt) = camera_viewport.single();
-
-        // Get viewport bounds in worldspace
-        let _left_bound = camera
-            .ndc_to_world(camera_gt, Vec3::new(-1.0, 0.0, 0.0))
-            .unwrap();
-        let _right_bound = camera
-            .ndc_to_world(camera_gt, Vec3::new(1.0, 0.0, 0.0))
-            .unwrap();
-
-        let current_chunk_index = Self::x_pos_to_chunk_index(camera_gt.translation().x as f64);
-        let i_min = current_chunk_index.wrapping_sub(terrain_chunk_count.0 as i128 / 2);
-        let i_max = current_chunk_index.wrapping_add(terrain_chunk_count.0 as i128 / 2);
-
-        //info!(
-        //    "current: {}, i_min: {}, i_max: {}",
-        //    current_chunk_index, i_min, i_max
-        //);
+        let streamed = Self::streamed_chunk_indices(&anchors, &terrain_chunk_count);
 
         // Filter Invalid sectors to despawn
         let invalid_sectors: Vec<(Entity, &TerrainChunk)> = terrain_chunks
             .iter()
-            .filter(|(_, chunk)| chunk.0 < i_min || chunk.0 > i_max)
+            .filter(|(_, chunk)| !streamed.contains(&chunk.0))
             .collect();
 
         if let Ok(terrain_id) = terrain.get_single() {
@@ -0,0 +1,100 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use super::{
+    components::TerrainDetail, generators::TerrainGenerator, plugin::WorldTerrainPlugin,
+    resources::ActiveTerrainGenerator, surfaces::SurfaceType,
+};
+
+// The terrain around a single x, as the bicycle feels it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerrainSample {
+    pub x: f64,
+    pub height: f64,
+    // Rise over run, positive uphill when riding right
+    pub slope: f64,
+    pub curvature: f64,
+    // None over a gap
+    pub surface: Option<SurfaceType>,
+}
+
+impl TerrainSample {
+    // Samples the same piecewise linear ground the near terrain colliders are
+    // built from, rather than the generator's smooth curve
+    pub fn at(generator: &dyn TerrainGenerator, x: f64) -> Self {
+        let width = segment_width();
+        let segment = (x / width).floor();
+        let (left_x, right_x) = (segment * width, (segment + 1.0) * width);
+        let (left, right) = (generator.height(left_x), generator.height(right_x));
+
+        let slope = (right - left) / width;
+        let height = left + slope * (x - left_x);
+
+        // Slope change across the neighbouring segments, over the arc length
+        let previous = (left - generator.height(left_x - width)) / width;
+        let next = (generator.height(right_x + width) - right) / width;
+        let curvature = (next - previous) / (2.0 * width) / (1.0 + slope * slope).powf(1.5);
+
+        let midpoint = left_x + width / 2.0;
+        let surface = (!generator.is_gap(midpoint)).then(|| generator.surface(midpoint));
+
+        TerrainSample {
+            x,
+            height,
+            slope,
+            curvature,
+            surface,
+        }
+    }
+
+    // Percent, as shown on road signs
+    pub fn grade(&self) -> f64 {
+        100.0 * self.slope
+    }
+
+    // Radians from horizontal
+    pub fn angle(&self) -> f64 {
+        self.slope.atan()
+    }
+
+    pub fn is_gap(&self) -> bool {
+        self.surface.is_none()
+    }
+}
+
+fn segment_width() -> f64 {
+    WorldTerrainPlugin::CHUNK_WIDTH as f64
+        / WorldTerrainPlugin::substep_count(TerrainDetail::Near) as f64
+}
+
+// Terrain under any x for the active generator, whether or not a chunk is
+// spawned there
+#[derive(SystemParam)]
+pub struct TerrainQuery<'w> {
+    generator: Res<'w, ActiveTerrainGenerator>,
+}
+
+impl TerrainQuery<'_> {
+    pub fn sample(&self, x: f64) -> TerrainSample {
+        TerrainSample::at(self.generator.0.as_ref(), x)
+    }
+
+    pub fn height(&self, x: f64) -> f64 {
+        self.sample(x).height
+    }
+
+    pub fn slope(&self, x: f64) -> f64 {
+        self.sample(x).slope
+    }
+
+    pub fn grade(&self, x: f64) -> f64 {
+        self.sample(x).grade()
+    }
+
+    pub fn curvature(&self, x: f64) -> f64 {
+        self.sample(x).curvature
+    }
+
+    pub fn surface(&self, x: f64) -> Option<SurfaceType> {
+        self.sample(x).surface
+    }
+}